@@ -0,0 +1,20 @@
+use notify_rust::Notification;
+use std::io::Write;
+
+/// Show a desktop notification for a console log line from a session.
+///
+/// The notification is shown from another thread, since that can block while talking to the
+/// desktop's notification service. Errors are ignored, since there may not be one at all.
+pub fn notify(session: &str, line: &str) {
+    let summary = format!("{} {}", env!("CARGO_PKG_NAME"), session);
+    let body = line.trim_end().to_string();
+    std::thread::spawn(move || {
+        let _ = Notification::new().summary(&summary).body(&body).show();
+    });
+}
+
+/// Ring the terminal bell.
+pub fn bell(out: &mut dyn Write) {
+    let _ = write!(out, "\x07");
+    let _ = out.flush();
+}
@@ -0,0 +1,173 @@
+use crate::config::Config;
+use crate::script::Step;
+use crate::session::load_keyring_pass;
+use crate::{
+    connect_within, read_pass_file, CliAuthError, ClientSettings, Target, EXIT_COMMAND_FAILED,
+};
+use northstar_rcon_client::FleetResult;
+use proc_exit::Code;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// Run a script on every profile in the config at the same time, showing the output from each
+/// server and then whether the script worked on it, and exit.
+pub async fn run_on_all(
+    config: &Config,
+    script: Option<Vec<Step>>,
+    use_keyring: bool,
+    wait: Duration,
+    connect_timeout: Option<Duration>,
+    settings: &ClientSettings,
+) -> ! {
+    let script = match script {
+        Some(script) => script,
+        None => {
+            eprintln!("--all needs commands to run, passed with --command or --file.");
+            Code::USAGE_ERR.process_exit();
+        }
+    };
+    if script
+        .iter()
+        .any(|step| matches!(step, Step::Run(line) if line.trim_start().starts_with('!')))
+    {
+        eprintln!("Builtins can't be run with --all.");
+        Code::USAGE_ERR.process_exit();
+    }
+    if config.profiles.is_empty() {
+        eprintln!("There are no profiles in the config file.");
+        Code::CONFIG_ERR.process_exit();
+    }
+
+    // Passwords that need to be entered are asked for before connecting to anything
+    let mut profile_names: Vec<&String> = config.profiles.keys().collect();
+    profile_names.sort();
+    let mut results = BTreeMap::new();
+    let mut servers = JoinSet::new();
+    let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+    for profile in profile_names {
+        let target = match Target::resolve(config, profile) {
+            Ok(target) => target,
+            Err(err) => {
+                results.insert(profile.clone(), Err(format!("Invalid address: {}", err)));
+                continue;
+            }
+        };
+        let pass = match read_pass(&target, use_keyring) {
+            Ok(pass) => pass,
+            Err(err) => {
+                results.insert(
+                    profile.clone(),
+                    Err(format!("Can't read password: {}", err)),
+                );
+                continue;
+            }
+        };
+
+        let script = script.clone();
+        let output = output_tx.clone();
+        let settings = settings.clone();
+        servers.spawn(async move {
+            let name = target.name.clone();
+            let result = run_on_server(
+                target,
+                pass,
+                script,
+                wait,
+                connect_timeout,
+                &settings,
+                output,
+            );
+            (name, result.await)
+        });
+    }
+    drop(output_tx);
+
+    loop {
+        select! {
+            Some((name, line)) = output_rx.recv() => println!("[{}] {}", name, line),
+            server = servers.join_next() => match server {
+                Some(Ok((name, result))) => {
+                    results.insert(name, result);
+                }
+                Some(Err(err)) => eprintln!("An error occurred: {}", err),
+                None => break,
+            },
+        }
+    }
+    while let Ok((name, line)) = output_rx.try_recv() {
+        println!("[{}] {}", name, line);
+    }
+
+    let results: FleetResult<String, String> = results.into_iter().collect();
+    if !results.is_empty() {
+        eprintln!("{}", results);
+    }
+
+    if results.all_ok() {
+        Code::SUCCESS.process_exit();
+    } else {
+        EXIT_COMMAND_FAILED.process_exit();
+    }
+}
+
+fn read_pass(target: &Target, use_keyring: bool) -> std::io::Result<String> {
+    if let Some(pass_file) = &target.pass_file {
+        return read_pass_file(pass_file);
+    }
+    if use_keyring {
+        if let Some(pass) = load_keyring_pass(&target.profile)? {
+            return Ok(pass);
+        }
+    }
+    rpassword::prompt_password(format!("{}'s password: ", target.name))
+}
+
+// Connect to a server and run the script, sending console logs to the output channel until
+// `wait` has passed after the last command
+async fn run_on_server(
+    target: Target,
+    pass: String,
+    script: Vec<Step>,
+    wait: Duration,
+    connect_timeout: Option<Duration>,
+    settings: &ClientSettings,
+    output: mpsc::UnboundedSender<(String, String)>,
+) -> Result<(), String> {
+    let client = connect_within(target.addr, connect_timeout, settings)
+        .await
+        .map_err(|err| format!("Connection failed: {}", err))?;
+    let (mut read, mut write) = client
+        .authenticate(&pass)
+        .await
+        .map_err(|(_, err)| format!("Authentication failed: {}", CliAuthError(err)))?;
+
+    let name = target.name;
+    let reader = tokio::spawn(async move {
+        while let Ok(line) = read.receive_console_log().await {
+            if output.send((name.clone(), line)).is_err() {
+                return;
+            }
+        }
+    });
+
+    let result = async {
+        for step in script {
+            match step {
+                Step::Run(line) => write
+                    .exec_command(&line)
+                    .await
+                    .map_err(|err| format!("Can't send {}: {}", line, err))?,
+                Step::Sleep(duration) => tokio::time::sleep(duration).await,
+            }
+        }
+        tokio::time::sleep(wait).await;
+        Ok(())
+    }
+    .await;
+
+    reader.abort();
+    result
+}
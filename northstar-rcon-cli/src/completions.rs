@@ -0,0 +1,36 @@
+use crate::config::Config;
+use clap::{CommandFactory, PossibleValue};
+use clap_complete::Shell;
+use std::path::Path;
+
+/// Print a completion script for a shell to stdout.
+///
+/// Besides the flags, the script completes the names of profiles in the config file as the
+/// address, and builtins as `--command`. The profiles are the ones that exist when the script is
+/// generated, so it needs to be generated again after changing them.
+pub fn print(shell: Shell, config: &Config) {
+    let mut profiles: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+    profiles.sort_unstable();
+
+    // Complete builtins by the name before their arguments, e.g. `!kick` for `!kick <PLAYER>`
+    let mut builtins: Vec<PossibleValue> = Vec::new();
+    for (usage, description) in crate::BUILTINS {
+        let name = usage.split_whitespace().next().unwrap();
+        if name.starts_with('!') && !builtins.iter().any(|value| value.get_name() == name) {
+            builtins.push(PossibleValue::new(name).help(description));
+        }
+    }
+
+    let mut command = crate::Args::command()
+        .mut_arg("address", |arg| arg.possible_values(profiles))
+        .mut_arg("commands", |arg| arg.possible_values(builtins));
+
+    // Complete the name the client was run as, since release builds are renamed
+    let name = std::env::args_os()
+        .next()
+        .as_ref()
+        .and_then(|path| Path::new(path).file_stem())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| command.get_name().to_string());
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+}
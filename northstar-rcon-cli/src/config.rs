@@ -0,0 +1,204 @@
+use northstar_rcon_client::{AuthMatchers, CommandFilter};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Settings loaded from the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Servers that can be connected to by name.
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+
+    /// Prompt to show, in the same format as `--prompt`.
+    pub prompt: Option<String>,
+
+    /// Short names for commands, expanded when they're the first word of a line.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Text servers use in authentication responses.
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    /// Commands and ConVars that can't be sent, or are asked about first.
+    #[serde(default)]
+    pub commands: CommandsConfig,
+}
+
+/// Text servers use in authentication responses, for servers that word them differently.
+///
+/// Each list replaces the client's default for it when set.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Text in a response that means the password was wrong.
+    pub invalid_password: Option<Vec<String>>,
+
+    /// Text in a response that means the client is banned.
+    pub banned: Option<Vec<String>>,
+}
+
+impl AuthConfig {
+    pub fn matchers(&self) -> AuthMatchers {
+        let mut matchers = AuthMatchers::default();
+        if let Some(invalid_password) = &self.invalid_password {
+            matchers.invalid_password = invalid_password.clone();
+        }
+        if let Some(banned) = &self.banned {
+            matchers.banned = banned.clone();
+        }
+        matchers
+    }
+}
+
+/// Glob patterns for the names of commands and ConVars that can be sent, checked before every
+/// request.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandsConfig {
+    /// If not empty, only names matching one of these can be sent.
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Names matching any of these can't be sent.
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Names to ask for confirmation before sending in the shell, instead of the default list.
+    pub dangerous: Option<Vec<String>>,
+}
+
+/// Commands that are asked about before they're sent, unless the config file has its own list.
+pub const DEFAULT_DANGEROUS: &[&str] = &[
+    "quit",
+    "exit",
+    "killserver",
+    "kick",
+    "kickid",
+    "ban",
+    "banid",
+    "addip",
+];
+
+impl CommandsConfig {
+    pub fn filter(&self) -> Option<CommandFilter> {
+        if self.allow.is_empty() && self.deny.is_empty() {
+            return None;
+        }
+
+        let filter = self
+            .allow
+            .iter()
+            .fold(CommandFilter::new(), |f, p| f.allow(p));
+        Some(self.deny.iter().fold(filter, |f, p| f.deny(p)))
+    }
+
+    /// A filter that denies the commands to ask about before sending.
+    pub fn dangerous(&self) -> CommandFilter {
+        match &self.dangerous {
+            Some(dangerous) => dangerous
+                .iter()
+                .fold(CommandFilter::new(), |f, p| f.deny(p)),
+            None => DEFAULT_DANGEROUS
+                .iter()
+                .fold(CommandFilter::new(), |f, p| f.deny(p)),
+        }
+    }
+}
+
+/// A server that can be connected to by name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Address of the server, with an optional port.
+    pub address: String,
+
+    /// File to read the password from. If not set, the password is prompted for.
+    pub pass_file: Option<String>,
+}
+
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl Config {
+    /// Load the config file from a path, or from the default location if no path is given.
+    ///
+    /// It's not an error for the file at the default location to not exist.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(err) => return Err(ConfigError::Io(path, err)),
+        };
+
+        toml::from_str(&contents).map_err(|err| ConfigError::Parse(path, err))
+    }
+}
+
+/// The default location of the config file, such as `~/.config/northstar-rcon/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join("northstar-rcon")
+            .join("config.toml"),
+    )
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "Can't read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "Invalid {}: {}", path.display(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandsConfig;
+    use northstar_rcon_client::Request;
+
+    fn is_dangerous(cmd: &str) -> bool {
+        let dangerous = CommandsConfig::default().dangerous();
+        dangerous.check(&Request::ExecCommand { cmd }).is_err()
+    }
+
+    #[test]
+    fn default_dangerous_commands() {
+        for cmd in [
+            "quit",
+            "quit'",
+            "quit(",
+            "say hi; quit",
+            "ban(x)",
+            "ban 1000123",
+        ] {
+            assert!(is_dangerous(cmd), "{:?}", cmd);
+        }
+        assert!(!is_dangerous("say \"quit\""));
+        assert!(!is_dangerous("status"));
+    }
+
+    #[test]
+    fn player_actions_are_dangerous() {
+        // What `!kick` and `!ban` send
+        assert!(is_dangerous("kickid 2"));
+        assert!(is_dangerous("kick Pilot"));
+        assert!(is_dangerous("ban 1000123"));
+    }
+}
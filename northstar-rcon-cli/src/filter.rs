@@ -0,0 +1,38 @@
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+
+/// Decides which console log lines are displayed.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    regex: Regex,
+    exclude: bool,
+}
+
+impl Filter {
+    /// Parse a filter from a regex. If the pattern starts with `!`, lines matching the rest of the
+    /// pattern are hidden instead of shown.
+    pub fn parse(pattern: &str) -> Result<Filter, regex::Error> {
+        let (pattern, exclude) = match pattern.strip_prefix('!') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+
+        Ok(Filter {
+            regex: Regex::new(pattern)?,
+            exclude,
+        })
+    }
+
+    pub fn allows(&self, line: &str) -> bool {
+        self.regex.is_match(line) != self.exclude
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.exclude {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.regex)
+    }
+}
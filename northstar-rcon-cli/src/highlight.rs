@@ -0,0 +1,22 @@
+use crossterm::style::{Color, Stylize};
+use northstar_rcon_client::{strip_tags, LogLevel};
+
+/// Color a console log line by its severity, with errors in red and warnings in yellow.
+///
+/// The bracketed tags at the start of the line, such as the time and where the line came from,
+/// are dimmed so the message stands out.
+pub fn highlight(line: &str) -> String {
+    let message = strip_tags(line);
+    let tags = &line[..line.len() - message.len()];
+    let message = match LogLevel::parse(line) {
+        Some(LogLevel::Error | LogLevel::Critical) => message.with(Color::Red).to_string(),
+        Some(LogLevel::Warning) => message.with(Color::Yellow).to_string(),
+        _ => message.to_string(),
+    };
+
+    if tags.is_empty() {
+        message
+    } else {
+        format!("{}{}", tags.dim(), message)
+    }
+}
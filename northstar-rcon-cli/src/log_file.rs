@@ -0,0 +1,38 @@
+use chrono::{SecondsFormat, Utc};
+use northstar_rcon_client::file_sink::FileSink;
+
+/// Copies console logs, and optionally the commands that are typed, to a file.
+///
+/// Every line is prefixed with a timestamp and the name of the session it belongs to.
+pub struct LogFile {
+    sink: FileSink,
+    log_commands: bool,
+}
+
+impl LogFile {
+    pub fn new(sink: FileSink, log_commands: bool) -> Self {
+        LogFile { sink, log_commands }
+    }
+
+    pub fn log_line(&mut self, session: &str, line: &str) -> std::io::Result<()> {
+        self.write(session, line)
+    }
+
+    /// Write a command typed into a session, if commands are being logged.
+    pub fn log_command(&mut self, session: &str, command: &str) -> std::io::Result<()> {
+        if self.log_commands && !command.is_empty() {
+            self.write(session, &format!("> {}", command))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write(&mut self, session: &str, text: &str) -> std::io::Result<()> {
+        self.sink.write_line(&format!(
+            "{} [{}] {}",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            session,
+            text
+        ))
+    }
+}
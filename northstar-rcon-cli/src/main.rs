@@ -1,19 +1,81 @@
-use crate::shell::{new_shell, ShellRead, ShellWrite};
+use crate::config::Config;
+use crate::filter::Filter;
+use crate::log_file::LogFile;
+use crate::maps::MapMatch;
+use crate::players::{Player, PlayerAction, PlayerMatch};
+use crate::progress::Progress;
+use crate::prompt::PromptTemplate;
+use crate::record::Recorder;
+use crate::script::Step;
+use crate::session::{
+    load_keyring_pass, save_keyring_pass, ConnectionStatus, PassSource, Session, SessionEvent,
+    Sessions,
+};
+use crate::shell::{new_shell, Input, ShellRead, ShellWrite};
+use chrono::{Local, Utc};
 use clap::Parser;
+use crossterm::cursor::MoveTo;
+use crossterm::execute;
 use crossterm::style::{Color, Stylize};
-use northstar_rcon_client::{connect, AuthError, ClientRead, ClientWrite};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::tty::IsTty;
+use northstar_rcon_client::file_sink::{FileSink, Rotation};
+use northstar_rcon_client::{
+    connect, AuthError, AuthMatchers, CommandEcho, CommandFilter, NotAuthenticatedClient, Request,
+    ServerInfo,
+};
+use proc_exit::Code;
+use regex::Regex;
 use rpassword::prompt_password;
 use std::fmt::{Display, Formatter};
+use std::io::Write;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use tokio::select;
+use tokio::sync::mpsc;
+use tracing_subscriber::filter::LevelFilter;
 
+mod alert;
+mod broadcast;
+mod completions;
+mod config;
+mod filter;
+mod highlight;
+mod log_file;
+mod maps;
+mod players;
+mod progress;
+mod prompt;
+mod record;
+mod scan;
+mod script;
+mod service;
+mod session;
 mod shell;
+#[cfg(windows)]
+mod win_service;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    /// Address of the Northstar server, e.g. `127.0.0.1:37015`.
-    address: String,
+    #[clap(subcommand)]
+    subcommand: Option<Subcommand>,
+
+    /// Address of the Northstar server, e.g. `127.0.0.1:37015`, or the name of a profile.
+    #[clap(required_unless_present = "all")]
+    address: Option<String>,
+
+    /// Run `--command` or `--file` on every profile at the same time, instead of one server.
+    #[clap(long, conflicts_with_all = &["address", "watch", "interactive"])]
+    all: bool,
 
     /// Name to display for the server in the prompt.
     #[clap(short, long)]
@@ -23,65 +85,523 @@ struct Args {
     #[clap(short, long)]
     pass_file: Option<String>,
 
+    /// Exit after entering a wrong password this many times, instead of asking again.
+    #[clap(long, value_name = "COUNT")]
+    max_auth_attempts: Option<u32>,
+
+    /// Save entered passwords in the OS keyring, and use them instead of prompting next time.
+    #[clap(long)]
+    use_keyring: bool,
+
     /// Force non-interactive script mode, even in interactive terminals.
     #[clap(long)]
     script_mode: bool,
+
+    /// Wait for each command's output and show it before sending the next command.
+    #[clap(long, conflicts_with = "watch")]
+    batch: bool,
+
+    /// Show how long the server took to echo each command back. Implies `--batch`.
+    #[clap(long, conflicts_with = "watch")]
+    show_rtt: bool,
+
+    /// Only show the output of commands that are run, instead of every console log line.
+    /// Implies `--batch`.
+    #[clap(short, long, conflicts_with_all = &["watch", "service"])]
+    quiet: bool,
+
+    /// Load profiles from a config file, instead of the default location.
+    #[clap(long, global = true)]
+    config: Option<String>,
+
+    /// Prompt to show, where `{name}` is the session name and `{map}` and `{players}` come from
+    /// polling `status`.
+    #[clap(long, value_name = "TEMPLATE")]
+    prompt: Option<String>,
+
+    /// Reconnect automatically when the connection to a server is lost.
+    #[clap(long)]
+    auto_reconnect: bool,
+
+    /// Forward console logs to journald and reconnect automatically, for running as a systemd
+    /// service. The password must come from a pass file or the keyring.
+    #[clap(long, conflicts_with_all = &["commands", "file", "interactive", "watch", "all"])]
+    service: bool,
+
+    /// Run as a Windows service, which works like `--service` but only writes logs to
+    /// `--log-file`.
+    #[cfg(windows)]
+    #[clap(
+        long,
+        requires = "log-file",
+        conflicts_with_all = &["commands", "file", "interactive", "watch", "all"]
+    )]
+    windows_service: bool,
+
+    /// Run a command and exit, instead of reading commands from the terminal. Can be repeated.
+    #[clap(short, long = "command", value_name = "COMMAND")]
+    commands: Vec<String>,
+
+    /// Run a command after connecting, then open the shell as usual. Can be repeated.
+    #[clap(short, long = "exec", value_name = "COMMAND", conflicts_with = "all")]
+    exec: Vec<String>,
+
+    /// Run commands from a script file and exit, instead of reading them from the terminal.
+    #[clap(short, long, value_name = "PATH", conflicts_with = "commands")]
+    file: Option<String>,
+
+    /// Open the shell after running `--command` or `--file`, instead of exiting.
+    #[clap(short, long)]
+    interactive: bool,
+
+    /// How long to wait for output after running `--command` or `--file`, in seconds.
+    #[clap(long, value_name = "SECS", default_value_t = 0., value_parser = parse_wait_secs)]
+    wait: f64,
+
+    /// Run a command every few seconds and show its output, like `watch`.
+    #[clap(
+        short,
+        long,
+        value_name = "COMMAND",
+        conflicts_with_all = &["commands", "file"]
+    )]
+    watch: Option<String>,
+
+    /// How often to run the `--watch` command, in seconds.
+    #[clap(
+        long,
+        value_name = "SECS",
+        requires = "watch",
+        default_value_t = 2.,
+        value_parser = parse_secs
+    )]
+    interval: f64,
+
+    /// Highlight lines of `--watch` output that changed since the previous run.
+    #[clap(short, long, requires = "watch")]
+    diff: bool,
+
+    /// Exit as soon as a command fails.
+    #[clap(long)]
+    fail_fast: bool,
+
+    /// Send dangerous commands, like `quit` or `ban`, without asking for confirmation first.
+    #[clap(short, long)]
+    yes: bool,
+
+    /// Exit with an error if the client is still running after this many seconds.
+    #[clap(long, value_name = "SECS", value_parser = parse_secs)]
+    timeout: Option<f64>,
+
+    /// How long to wait for the connection to a server to open, in seconds.
+    #[clap(long, value_name = "SECS", value_parser = parse_secs)]
+    connect_timeout: Option<f64>,
+
+    /// Show the time each console log line was received, in local time or UTC.
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "ZONE",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        default_missing_value = "local"
+    )]
+    timestamps: Option<Timestamps>,
+
+    /// Don't color console log lines or other output. Setting `NO_COLOR` does the same.
+    #[clap(long)]
+    no_color: bool,
+
+    /// Show what the client is doing on stderr. Repeat for more detail.
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Show every message sent to and received from servers on stderr, with passwords hidden.
+    #[clap(long)]
+    trace: bool,
+
+    /// Only display console log lines matching a regex, or not matching it if it starts with `!`.
+    #[clap(long, value_name = "PATTERN", value_parser = Filter::parse)]
+    filter: Option<Filter>,
+
+    /// Show a desktop notification when a console log line matches a regex. Can be repeated.
+    #[clap(long = "notify", value_name = "PATTERN", value_parser = Regex::new)]
+    notify: Vec<Regex>,
+
+    /// Ring the terminal bell when a console log line matches a regex. Can be repeated.
+    #[clap(long = "bell", value_name = "PATTERN", value_parser = Regex::new)]
+    bell: Vec<Regex>,
+
+    /// Copy console logs to a file, as well as displaying them.
+    #[clap(long, value_name = "PATH")]
+    log_file: Option<String>,
+
+    /// Also copy commands that are typed to the log file.
+    #[clap(long, requires = "log-file")]
+    log_commands: bool,
+
+    /// Start a new log file once it would grow beyond this many bytes.
+    #[clap(
+        long,
+        value_name = "BYTES",
+        requires = "log-file",
+        conflicts_with = "log-rotate-interval"
+    )]
+    log_rotate_size: Option<u64>,
+
+    /// Start a new log file after this many seconds.
+    #[clap(long, value_name = "SECS", requires = "log-file")]
+    log_rotate_interval: Option<u64>,
+
+    /// Number of old log files to keep when rotating.
+    #[clap(long, value_name = "COUNT", requires = "log-file", default_value_t = 5)]
+    log_max_files: usize,
+
+    /// Compress old log files with gzip when rotating.
+    #[clap(long, requires = "log-file")]
+    log_compress: bool,
 }
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() {
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+    /// Print a script that completes arguments, profiles and builtins in a shell.
+    Completions {
+        /// Shell to print the script for.
+        #[clap(value_parser)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Look for RCON servers on a network and list them with how long they took to answer.
+    ///
+    /// Each address is sent an authentication request with a password that won't work, so the
+    /// servers will log a failed attempt. The connect timeout is 1 second unless
+    /// --connect-timeout is passed.
+    Scan {
+        /// Network to scan, e.g. `10.0.0.0/24`. Defaults to the /24 this machine is on.
+        #[clap(value_name = "CIDR")]
+        network: Option<String>,
+
+        /// Port or range of ports to try on each address.
+        #[clap(long, value_name = "PORTS", default_value = "37015-37020", value_parser = scan::parse_ports)]
+        ports: RangeInclusive<u16>,
+    },
+
+    /// Connect to a server like usual, recording the commands that are run and the console logs
+    /// that come back to a file.
+    ///
+    /// Other options, like --pass-file, are passed before `record`.
+    Record {
+        /// File to write the recording to.
+        #[clap(short, long, value_name = "FILE")]
+        out: String,
+
+        /// Address of the Northstar server, or the name of a profile.
+        #[clap(value_parser)]
+        address: String,
+    },
+
+    /// Print a recording made with `record`, at the speed it happened.
+    Replay {
+        /// Recording to print.
+        #[clap(value_parser)]
+        file: String,
+
+        /// How many times faster than real time to replay the recording.
+        #[clap(long, value_name = "FACTOR", default_value_t = 1.0, value_parser)]
+        speed: f64,
+    },
+}
+
+#[derive(clap::ArgEnum, Debug, Clone, Copy)]
+enum Timestamps {
+    Local,
+    Utc,
+}
+
+impl Timestamps {
+    fn now(self) -> String {
+        match self {
+            Timestamps::Local => Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            Timestamps::Utc => Utc::now().format("%Y-%m-%d %H:%M:%SZ").to_string(),
+        }
+    }
+}
+
+fn main() {
     let args = Args::parse();
 
-    // Try to parse address with port, if that fails try to parse without and default to 37015.
-    let socket_addr: SocketAddr = match parse_string_addr(&args.address) {
-        Ok(addr) => addr,
+    #[cfg(windows)]
+    if args.windows_service {
+        win_service::start();
+        return;
+    }
+
+    run(args);
+}
+
+// The runtime is started by hand, since a Windows service runs the client on its own thread
+fn run(args: Args) {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(run_client(args));
+}
+
+async fn run_client(mut args: Args) {
+    #[cfg(windows)]
+    let service_mode = args.service || args.windows_service;
+    #[cfg(not(windows))]
+    let service_mode = args.service;
+
+    if let Some(timeout) = args.timeout {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs_f64(timeout)).await;
+            eprintln!("Timed out after {} seconds.", timeout);
+            EXIT_TIMED_OUT.process_exit();
+        });
+    }
+    let connect_timeout = args.connect_timeout.map(Duration::from_secs_f64);
+
+    if args.no_color {
+        crossterm::style::force_color_output(false);
+    }
+    let color = !args.no_color
+        && std::env::var("NO_COLOR").unwrap_or_default().is_empty()
+        && std::io::stdout().is_tty();
+
+    let max_level = match (args.trace, args.verbose) {
+        (true, _) => LevelFilter::TRACE,
+        (false, 0) => LevelFilter::OFF,
+        (false, 1) => LevelFilter::INFO,
+        (false, _) => LevelFilter::DEBUG,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(max_level)
+        .with_writer(std::io::stderr)
+        .with_ansi(color)
+        .init();
+
+    let config = match Config::load(args.config.as_deref().map(Path::new)) {
+        Ok(config) => config,
         Err(err) => {
-            eprintln!("Invalid address {}: {}", args.address, err);
-            proc_exit::Code::SERVICE_UNAVAILABLE.process_exit();
+            eprintln!("{}", err);
+            proc_exit::Code::CONFIG_ERR.process_exit();
         }
     };
+    let settings = ClientSettings::from_config(&config);
 
-    // Read the automated password, if one was supplied somehow.
-    let automated_password =
-        args.pass_file
-            .as_ref()
-            .map(|pass_file| match std::fs::read_to_string(pass_file) {
-                Ok(pass) => pass.trim().to_string(),
+    if let Some(Subcommand::Completions { shell }) = args.subcommand {
+        completions::print(shell, &config);
+        Code::SUCCESS.process_exit();
+    }
+    if let Some(Subcommand::Scan { network, ports }) = &args.subcommand {
+        scan::run(
+            network.as_deref(),
+            ports.clone(),
+            connect_timeout,
+            &settings,
+        )
+        .await;
+    }
+    if let Some(Subcommand::Replay { file, speed }) = &args.subcommand {
+        if *speed <= 0. {
+            eprintln!("--speed must be more than 0.");
+            Code::USAGE_ERR.process_exit();
+        }
+        record::replay(file, *speed).await;
+    }
+    if let Some(Subcommand::Record { address, .. }) = &args.subcommand {
+        args.address = Some(address.clone());
+    }
+
+    // Read commands to run before the shell, if there are any
+    let script = match &args.file {
+        Some(file) => {
+            let contents = match std::fs::read_to_string(file) {
+                Ok(contents) => contents,
                 Err(err) => {
-                    eprintln!("Can't read pass file: {}", err);
-                    proc_exit::Code::IO_ERR.process_exit();
+                    eprintln!("Can't read script file: {}", err);
+                    EXIT_SCRIPT_UNREADABLE.process_exit();
                 }
-            });
+            };
+            match script::parse(&contents) {
+                Ok(script) => Some(script),
+                Err(err) => {
+                    eprintln!("Invalid script file: {}", err);
+                    EXIT_SCRIPT_INVALID.process_exit();
+                }
+            }
+        }
+        None if !args.commands.is_empty() => {
+            Some(args.commands.iter().cloned().map(Step::Run).collect())
+        }
+        None => None,
+    };
+
+    // clap requires an address unless `--all` is passed
+    let address = match &args.address {
+        Some(address) => address,
+        None => {
+            let wait = Duration::from_secs_f64(args.wait);
+            let use_keyring = args.use_keyring;
+            broadcast::run_on_all(
+                &config,
+                script,
+                use_keyring,
+                wait,
+                connect_timeout,
+                &settings,
+            )
+            .await
+        }
+    };
+
+    // Show what's happening while connecting, since each stage can take a while
+    let show_progress = std::io::stderr().is_tty() && !service_mode;
+    let mut progress = Progress::start(format!("Resolving {}", address), show_progress);
+
+    let mut target = match Target::resolve(&config, address) {
+        Ok(target) => target,
+        Err(err) => {
+            progress.finish();
+            eprintln!("Invalid address {}: {}", address, err);
+            EXIT_CONNECTION_FAILED.process_exit();
+        }
+    };
+    if let Some(name) = args.name {
+        target.name = name;
+    }
+    if args.pass_file.is_some() {
+        target.pass_file = args.pass_file;
+    }
+
+    let log_file = args.log_file.map(|log_file| {
+        let rotation = match (args.log_rotate_size, args.log_rotate_interval) {
+            (Some(size), _) => Rotation::Size(size),
+            (None, Some(interval)) => Rotation::Interval(Duration::from_secs(interval)),
+            (None, None) => Rotation::Never,
+        };
+
+        match FileSink::open(&log_file) {
+            Ok(sink) => LogFile::new(
+                sink.rotation(rotation)
+                    .max_files(args.log_max_files)
+                    .compress(args.log_compress),
+                args.log_commands,
+            ),
+            Err(err) => {
+                progress.finish();
+                eprintln!("Can't open log file: {}", err);
+                proc_exit::Code::IO_ERR.process_exit();
+            }
+        }
+    });
 
-    let name = args.name.unwrap_or_else(|| socket_addr.to_string());
+    let recorder = match &args.subcommand {
+        Some(Subcommand::Record { out, .. }) => match Recorder::create(out) {
+            Ok(recorder) => Some(recorder),
+            Err(err) => {
+                progress.finish();
+                eprintln!("Can't open recording: {}", err);
+                proc_exit::Code::IO_ERR.process_exit();
+            }
+        },
+        _ => None,
+    };
 
-    let mut client = match connect(socket_addr).await {
+    // Read the automated password, if one was supplied somehow.
+    let automated_password = match &target.pass_file {
+        Some(pass_file) => match read_pass_file(pass_file) {
+            Ok(pass) => Some((pass, PassSource::File(pass_file.clone()))),
+            Err(err) => {
+                progress.finish();
+                eprintln!("Can't read pass file: {}", err);
+                proc_exit::Code::IO_ERR.process_exit();
+            }
+        },
+        None if args.use_keyring => match load_keyring_pass(&target.profile) {
+            Ok(pass) => pass.map(|pass| (pass, PassSource::Keyring(target.profile.clone()))),
+            Err(err) => {
+                progress.finish();
+                eprintln!("Can't read password from keyring: {}", err);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // There's nobody to type a password when running as a service
+    if service_mode && automated_password.is_none() {
+        eprintln!("A pass file or a password saved in the keyring is needed to run as a service.");
+        Code::USAGE_ERR.process_exit();
+    }
+
+    progress.stage(format!("Connecting to {}", target.addr));
+    let client = match connect_within(target.addr, connect_timeout, &settings).await {
         Ok(client) => client,
         Err(err) => {
-            eprintln!("Connection failed: {}", err);
-            proc_exit::Code::SERVICE_UNAVAILABLE.process_exit();
+            progress.finish();
+            eprintln!("Connection to {} failed: {}", target.addr, err);
+            EXIT_CONNECTION_FAILED.process_exit();
         }
     };
 
-    let (client_read, client_write) = match &automated_password {
-        Some(pass) => match client.authenticate(pass).await {
-            Ok(halves) => halves,
-            Err((_, err)) => {
-                eprintln!("Authentication failed: {}", CliAuthError(err));
-                proc_exit::Code::SERVICE_UNAVAILABLE.process_exit();
+    // Try the automated password first, keeping the client to prompt with if it fails
+    progress.stage("Authenticating");
+    let authenticated = match automated_password {
+        Some((pass, pass_source)) => match client.authenticate(&pass).await {
+            Ok((read, write)) => Ok((read, write, pass_source)),
+            Err((new_client, err)) => {
+                progress.finish();
+                let err = CliAuthError(err);
+
+                // A password saved in the keyring may be out of date, so ask for the new one
+                if matches!(pass_source, PassSource::Keyring(_)) && !err.is_fatal() {
+                    eprintln!("The password saved in the keyring is wrong.");
+                    Err(new_client)
+                } else {
+                    eprintln!("Authentication failed: {}", err);
+                    err.exit_code().process_exit();
+                }
             }
         },
-        None => loop {
-            let pass = prompt_password(format!("{}'s password: ", name)).unwrap();
+        None => Err(client),
+    };
 
-            match client.authenticate(&pass).await {
-                Ok(halves) => break halves,
+    progress.finish();
+
+    let mut attempts = 0;
+    let (client_read, client_write, pass_source) = match authenticated {
+        Ok(authenticated) => authenticated,
+        Err(mut client) => loop {
+            if args.max_auth_attempts == Some(attempts) {
+                eprintln!("Giving up after {} password attempts.", attempts);
+                EXIT_TOO_MANY_ATTEMPTS.process_exit();
+            }
+            attempts += 1;
+
+            let pass = prompt_password(format!("{}'s password: ", target.name)).unwrap();
+
+            let mut progress = Progress::start("Authenticating", show_progress);
+            let authenticated = client.authenticate(&pass).await;
+            progress.finish();
+            match authenticated {
+                Ok((read, write)) => {
+                    if args.use_keyring {
+                        if let Err(err) = save_keyring_pass(&target.profile, &pass) {
+                            eprintln!("Can't save password to keyring: {}", err);
+                        }
+                    }
+                    break (read, write, PassSource::Entered(pass));
+                }
                 Err((new_client, err)) => {
                     let err = CliAuthError(err);
                     eprintln!("{}", err);
 
                     if err.is_fatal() {
-                        proc_exit::Code::SERVICE_UNAVAILABLE.process_exit();
+                        err.exit_code().process_exit();
                     } else {
                         client = new_client;
                     }
@@ -90,15 +610,308 @@ async fn main() {
         },
     };
 
-    let (shell_read, shell_write) = new_shell(format!("{}> ", name), args.script_mode);
+    let mut client_write = client_write;
+    if service_mode {
+        if let Err(err) = client_write.enable_console_logs().await {
+            eprintln!("Can't enable console logs: {}", err);
+            EXIT_COMMAND_FAILED.process_exit();
+        }
+        if let Err(err) = service::notify_ready() {
+            eprintln!("Can't notify systemd: {}", err);
+        }
+    }
+
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    let mut sessions = Sessions::new(events_tx, settings);
+    sessions.open(
+        target.name.clone(),
+        target.addr,
+        pass_source,
+        client_read,
+        client_write,
+    );
+    if service_mode {
+        sessions.current().unwrap().set_console_logs();
+    }
+
+    let prompt = PromptTemplate::new(
+        args.prompt
+            .or_else(|| config.prompt.clone())
+            .unwrap_or_else(|| prompt::DEFAULT_TEMPLATE.to_string()),
+    );
+
+    let (stdin, stdout) = new_shell(
+        prompt.render(sessions.current().unwrap()),
+        args.script_mode
+            || (script.is_some() && !args.interactive)
+            || args.watch.is_some()
+            || service_mode,
+    );
+
+    let mut cli = Cli {
+        config,
+        auto_reconnect: args.auto_reconnect || service_mode,
+        service: service_mode,
+        use_keyring: args.use_keyring,
+        connect_timeout,
+        fail_fast: args.fail_fast,
+        confirm: None,
+        batch: args.batch,
+        show_rtt: args.show_rtt,
+        quiet: args.quiet,
+        command_failed: false,
+        timestamps: args.timestamps,
+        color,
+        filter: args.filter,
+        notify: args.notify,
+        bell: args.bell,
+        prompt,
+        sessions,
+        events: events_rx,
+        stdin,
+        stdout,
+        pending_line: None,
+        log_file,
+        recorder,
+    };
+
+    for line in &args.exec {
+        cli.run_line(line).await;
+    }
+
+    if let Some(command) = &args.watch {
+        cli.watch(command, Duration::from_secs_f64(args.interval), args.diff)
+            .await;
+    }
+
+    if let Some(script) = script {
+        for step in script {
+            match step {
+                Step::Run(line) => cli.run_line(&line).await,
+                Step::Sleep(duration) => cli.show_output_for(duration).await,
+            }
+        }
+
+        cli.show_output_for(Duration::from_secs_f64(args.wait))
+            .await;
+
+        if !args.interactive {
+            cli.finish();
+        }
+    }
+
+    // Only commands typed into the shell are asked about, when someone is there to answer
+    if !args.yes && cli.stdin.is_interactive() {
+        cli.confirm = Some(cli.config.commands.dangerous());
+    }
+
+    let poll_status = cli.stdin.is_interactive() && cli.prompt.needs_status();
+    let mut status_poll = tokio::time::interval(STATUS_POLL_INTERVAL);
+
+    loop {
+        if let Some(line) = cli.pending_line.take() {
+            cli.run_input(&line).await;
+        }
+
+        select! {
+            // Receive REPL inputs
+            input = cli.stdin.read_line(), if !cli.service => match input {
+                Some(Input::Line(line)) => cli.run_input(&line).await,
+                Some(Input::Interrupted) => cli.show_interrupt_hint(),
+                None => cli.finish(),
+            },
+
+            // Log incoming lines
+            Some((name, event)) = cli.events.recv() => {
+                cli.handle_event(&name, event);
+            }
+
+            // Keep the server details in the prompt up to date
+            _ = status_poll.tick(), if poll_status => cli.poll_status().await,
+        }
+    }
+}
+
+// Exit codes, so scripts can tell what went wrong
+const EXIT_CONNECTION_FAILED: Code = Code::SERVICE_UNAVAILABLE;
+const EXIT_AUTH_FAILED: Code = Code::NO_PERM;
+const EXIT_COMMAND_FAILED: Code = Code::IO_ERR;
+const EXIT_CONNECTION_CLOSED: Code = Code::PROTOCOL_ERR;
+const EXIT_SCRIPT_INVALID: Code = Code::DATA_ERR;
+const EXIT_SCRIPT_UNREADABLE: Code = Code::NO_INPUT;
+const EXIT_TIMED_OUT: Code = Code::TEMP_FAIL;
+const EXIT_TOO_MANY_ATTEMPTS: Code = Code::NO_USER;
+
+/// How often `status` is run for prompts that show details about the server.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long after polling `status` its output is hidden for.
+const STATUS_POLL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for more output from a command like `!page` before using what's arrived.
+const CAPTURE_QUIET_TIME: Duration = Duration::from_millis(500);
+
+/// The longest time to collect output from a command for, in case the server never goes quiet.
+const CAPTURE_MAX_TIME: Duration = Duration::from_secs(10);
 
-    select! {
-        // Start logging incoming lines
-        _ = log_loop(client_read, shell_write.clone()) => {},
+/// Usage and description of each builtin, shown by `!help`.
+const BUILTINS: &[(&str, &str)] = &[
+    ("!help", "View this help listing"),
+    ("!connect <PROFILE> [NAME]", "Open another session"),
+    ("!reconnect", "Reconnect the current session"),
+    ("!switch <NAME>", "Make another session the current one"),
+    ("!sessions", "List open sessions"),
+    (
+        "!broadcast <COMMAND>",
+        "Run a command on every connected session",
+    ),
+    ("!enable console", "Enable server console logging"),
+    ("!filter <PATTERN>", "Only show logs matching a regex"),
+    (
+        "!alias [NAME] [COMMAND]",
+        "List, show or define command aliases",
+    ),
+    (
+        "!page <COMMAND>",
+        "Run a command and show its output in a pager",
+    ),
+    ("!status", "Show a summary of the server's status"),
+    ("!players", "List the players on the server"),
+    ("!kick <PLAYER>", "Kick a player by name or UID"),
+    ("!ban <PLAYER>", "Ban a player by name or UID"),
+    ("!map <MAP>", "Change map, finding it by part of its name"),
+    ("!quit", "Quit this session"),
+    ("!set <VAR> <VAL>", "Set a ConVar on the server"),
+    ("<COMMAND> [ARGS...]", "Run a command on the server"),
+];
+
+/// A server to connect to.
+struct Target {
+    name: String,
+    /// The profile name or address the target was found from, which its keyring password is
+    /// saved under.
+    profile: String,
+    addr: SocketAddr,
+    pass_file: Option<String>,
+}
+
+impl Target {
+    /// Find the server for a profile name or address.
+    fn resolve(config: &Config, profile_or_addr: &str) -> std::io::Result<Target> {
+        match config.profiles.get(profile_or_addr) {
+            Some(profile) => Ok(Target {
+                name: profile_or_addr.to_string(),
+                profile: profile_or_addr.to_string(),
+                addr: parse_string_addr(&profile.address)?,
+                pass_file: profile.pass_file.clone(),
+            }),
+            None => {
+                let addr = parse_string_addr(profile_or_addr)?;
+                Ok(Target {
+                    name: addr.to_string(),
+                    profile: addr.to_string(),
+                    addr,
+                    pass_file: None,
+                })
+            }
+        }
+    }
+}
 
-        // Start receiving REPL inputs
-        _ = repl_loop(client_write, shell_read, shell_write) => {},
+/// Show lines in the program set by `$PAGER`, or `less` if it isn't set, and wait for it to exit.
+fn run_pager(lines: &[String]) -> std::io::Result<()> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut args = pager.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "$PAGER is empty"))?;
+
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+
+    // The pager may quit before reading everything, which isn't an error
+    let mut stdin = child.stdin.take().unwrap();
+    for line in lines {
+        if writeln!(stdin, "{}", line).is_err() {
+            break;
+        }
+    }
+    drop(stdin);
+
+    child.wait()?;
+    Ok(())
+}
+
+/// Settings from the config file that every connection is made with.
+#[derive(Debug, Clone)]
+struct ClientSettings {
+    /// The text used to read authentication responses, from the `[auth]` section.
+    auth_matchers: AuthMatchers,
+    /// The filter checked before sending anything, from the `[commands]` section.
+    command_filter: Option<CommandFilter>,
+}
+
+impl ClientSettings {
+    fn from_config(config: &Config) -> Self {
+        ClientSettings {
+            auth_matchers: config.auth.matchers(),
+            command_filter: config.commands.filter(),
+        }
+    }
+}
+
+/// Connect to a server, failing if the connection doesn't open within the timeout.
+async fn connect_within(
+    addr: SocketAddr,
+    timeout: Option<Duration>,
+    settings: &ClientSettings,
+) -> northstar_rcon_client::Result<NotAuthenticatedClient> {
+    let client = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect(addr))
+            .await
+            .unwrap_or_else(|_| Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into())),
+        None => connect(addr).await,
     };
+    let client = client?.auth_matchers(settings.auth_matchers.clone());
+    Ok(match settings.command_filter.clone() {
+        Some(filter) => client.command_filter(filter),
+        None => client,
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn read_pass_file(pass_file: &str) -> std::io::Result<String> {
+    Ok(std::fs::read_to_string(pass_file)?.trim().to_string())
+}
+
+/// Parse a number of seconds that's more than 0 and fits in a [`Duration`].
+fn parse_secs(secs: &str) -> Result<f64, String> {
+    match parse_duration_secs(secs) {
+        Some((secs, duration)) if !duration.is_zero() => Ok(secs),
+        _ => Err("must be a number of seconds more than 0".to_string()),
+    }
+}
+
+/// Like [`parse_secs`], but also accepting 0.
+fn parse_wait_secs(secs: &str) -> Result<f64, String> {
+    match parse_duration_secs(secs) {
+        Some((secs, _)) => Ok(secs),
+        None => Err("must be a number of seconds that isn't negative".to_string()),
+    }
+}
+
+fn parse_duration_secs(secs: &str) -> Option<(f64, Duration)> {
+    let secs: f64 = secs.trim().parse().ok()?;
+    Some((secs, Duration::try_from_secs_f64(secs).ok()?))
 }
 
 fn parse_socket_addr(to: impl ToSocketAddrs) -> std::io::Result<SocketAddr> {
@@ -122,8 +935,15 @@ struct CliAuthError(AuthError);
 impl CliAuthError {
     fn is_fatal(&self) -> bool {
         match &self.0 {
-            AuthError::InvalidPassword => false,
-            AuthError::Banned | AuthError::Fatal(_) => true,
+            AuthError::InvalidPassword { .. } => false,
+            AuthError::Banned { .. } | AuthError::Fatal(_) => true,
+        }
+    }
+
+    fn exit_code(&self) -> Code {
+        match &self.0 {
+            AuthError::InvalidPassword { .. } | AuthError::Banned { .. } => EXIT_AUTH_FAILED,
+            AuthError::Fatal(_) => EXIT_CONNECTION_FAILED,
         }
     }
 }
@@ -131,83 +951,1112 @@ impl CliAuthError {
 impl Display for CliAuthError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match &self.0 {
-            AuthError::InvalidPassword => write!(f, "Invalid password."),
-            AuthError::Banned => write!(f, "You are banned from this server."),
+            AuthError::InvalidPassword { message } => {
+                write!(f, "Invalid password.{}", server_said(message))
+            }
+            AuthError::Banned { message } => {
+                write!(
+                    f,
+                    "You are banned from this server.{}",
+                    server_said(message)
+                )
+            }
             AuthError::Fatal(err) => write!(f, "Connection failed: {}", err),
         }
     }
 }
 
-async fn log_loop(mut client_read: ClientRead, mut stdout: ShellWrite) -> ! {
-    loop {
-        match client_read.receive_console_log().await {
-            Ok(log) => writeln!(stdout.out(), "{}", log).unwrap(),
-            Err(err) => {
-                eprintln!("Connection closed: {}", err);
-                proc_exit::Code::SERVICE_UNAVAILABLE.process_exit();
+// Show the server's own wording after an authentication error, if it sent any
+fn server_said(message: &str) -> String {
+    match message.trim() {
+        "" => String::new(),
+        message => format!(" The server said: \"{}\"", message),
+    }
+}
+
+/// State of the interactive shell once the first session is open.
+struct Cli {
+    config: Config,
+    auto_reconnect: bool,
+    /// Whether logs are forwarded to journald and standard input is ignored.
+    service: bool,
+    use_keyring: bool,
+    connect_timeout: Option<Duration>,
+    fail_fast: bool,
+    /// Commands to ask about before sending, denied by the filter. `None` if nothing is asked.
+    confirm: Option<CommandFilter>,
+    batch: bool,
+    show_rtt: bool,
+    quiet: bool,
+    command_failed: bool,
+    timestamps: Option<Timestamps>,
+    color: bool,
+    filter: Option<Filter>,
+    /// Patterns that show a desktop notification when a log line matches.
+    notify: Vec<Regex>,
+    /// Patterns that ring the terminal bell when a log line matches.
+    bell: Vec<Regex>,
+    prompt: PromptTemplate,
+    sessions: Sessions,
+    events: mpsc::UnboundedReceiver<(String, SessionEvent)>,
+    stdin: ShellRead,
+    stdout: ShellWrite,
+    /// A line that was entered while a command's output was being collected, to run next.
+    pending_line: Option<String>,
+    log_file: Option<LogFile>,
+    recorder: Option<Recorder>,
+}
+
+impl Cli {
+    /// Show log lines and handle other session events for a while.
+    async fn show_output_for(&mut self, duration: Duration) {
+        let sleep = tokio::time::sleep(duration);
+        tokio::pin!(sleep);
+
+        loop {
+            select! {
+                _ = &mut sleep => return,
+                Some((name, event)) = self.events.recv() => {
+                    self.handle_event(&name, event);
+                }
             }
         }
     }
-}
 
-async fn repl_loop(
-    mut client_write: ClientWrite,
-    mut stdin: ShellRead,
-    mut stdout: ShellWrite,
-) -> ! {
-    loop {
-        let line = stdin.read_line().await;
+    fn handle_event(&mut self, name: &str, event: SessionEvent) {
+        let changes_status = !matches!(event, SessionEvent::Log(_));
+
+        match event {
+            SessionEvent::Log(log) => {
+                if self.update_status(name, &log) {
+                    return;
+                }
+                if self.notify.iter().any(|pattern| pattern.is_match(&log)) {
+                    alert::notify(name, &log);
+                }
+                if self.bell.iter().any(|pattern| pattern.is_match(&log)) {
+                    alert::bell(self.stdout.err());
+                }
+
+                // Quiet mode only shows output collected for commands, but still logs everything
+                if self.record_log(name, &log) && !self.quiet {
+                    self.show_log(name, log);
+                }
+                return;
+            }
+            SessionEvent::Closed(err) => {
+                // The session may have been reconnected manually after the connection was lost
+                if !self.sessions.is_connected(name) {
+                    return;
+                }
+
+                if self.auto_reconnect {
+                    writeln!(
+                        self.stdout.err(),
+                        "Connection to {} closed: {}. Reconnecting...",
+                        name,
+                        err
+                    )
+                    .unwrap();
+                    if let Err(err) = self.sessions.reconnect(name) {
+                        self.close_session(name, &format!("Can't read pass file: {}", err));
+                    }
+                } else if self.stdin.is_interactive() {
+                    self.sessions.disconnect(name, &err);
+                    writeln!(
+                        self.stdout.err(),
+                        "Connection to {} closed: {}. Use !reconnect to connect again.",
+                        name,
+                        err
+                    )
+                    .unwrap();
+                } else {
+                    self.close_session(name, &err);
+                }
+            }
+            SessionEvent::Reconnected(halves) => {
+                let (read, write) = *halves;
+                self.sessions.attach(name, read, write);
+                writeln!(self.stdout.err(), "Reconnected to {}.", name).unwrap();
+            }
+            SessionEvent::ReconnectFailed(err) => {
+                if self.stdin.is_interactive() {
+                    self.sessions.disconnect(name, &err);
+                    writeln!(
+                        self.stdout.err(),
+                        "Reconnecting to {} failed: {}",
+                        name,
+                        err
+                    )
+                    .unwrap();
+                } else {
+                    self.close_session(name, &err);
+                }
+            }
+        }
+
+        if changes_status {
+            self.update_prompt();
+        }
+    }
+
+    fn show_log(&mut self, name: &str, log: String) {
+        if self.service {
+            writeln!(self.stdout.out(), "{}", service::journal_line(&log)).unwrap();
+            return;
+        }
+
+        let log = if self.color {
+            highlight::highlight(&log)
+        } else {
+            log
+        };
+
+        let out = self.stdout.out();
+        if let Some(timestamps) = self.timestamps {
+            write!(out, "{} ", timestamps.now()).unwrap();
+        }
+
+        // Show where lines came from if there's more than one place they could come from
+        if self.sessions.len() > 1 {
+            writeln!(out, "[{}] {}", name, log).unwrap();
+        } else {
+            writeln!(out, "{}", log).unwrap();
+        }
+    }
+
+    // Write a log line to the log file, and return whether it should be displayed
+    fn record_log(&mut self, name: &str, log: &str) -> bool {
+        if let Some(log_file) = &mut self.log_file {
+            if let Err(err) = log_file.log_line(name, log) {
+                writeln!(self.stdout.err(), "Can't write to log file: {}", err).unwrap();
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.record_line(name, log) {
+                writeln!(self.stdout.err(), "Can't write to recording: {}", err).unwrap();
+            }
+        }
+
+        // Filtered out lines are still written to the log file
+        match &self.filter {
+            Some(filter) => filter.allows(log),
+            None => true,
+        }
+    }
+
+    /// Run a command repeatedly, showing the log lines that arrive after each run on their own.
+    ///
+    /// If `diff` is set, lines that are different from the same line in the previous run are
+    /// highlighted.
+    async fn watch(&mut self, command: &str, interval: Duration, diff: bool) -> ! {
+        let clear = std::io::stdout().is_tty();
+        let mut ticks = tokio::time::interval(interval);
+        let mut previous_lines = Vec::new();
+        let mut lines = Vec::new();
+        let mut runs = 0;
+
+        loop {
+            select! {
+                _ = ticks.tick() => {
+                    previous_lines = std::mem::take(&mut lines);
+                    runs += 1;
+
+                    let mut out = self.stdout.out();
+                    if clear {
+                        execute!(&mut out, Clear(ClearType::All), MoveTo(0, 0)).unwrap();
+                    }
+                    writeln!(
+                        out,
+                        "Every {}s: {}    {}\n",
+                        interval.as_secs_f64(),
+                        command,
+                        Local::now().format("%Y-%m-%d %H:%M:%S")
+                    )
+                    .unwrap();
+
+                    self.run_line(command).await;
+                }
+                Some((name, event)) = self.events.recv() => match event {
+                    SessionEvent::Log(log) => {
+                        if !self.record_log(&name, &log) {
+                            continue;
+                        }
+
+                        let line = log.trim_end().to_string();
+                        let out = self.stdout.out();
+                        // Nothing is highlighted on the first run
+                        let changed = runs > 1 && previous_lines.get(lines.len()) != Some(&line);
+                        if diff && changed {
+                            writeln!(out, "{}", line.as_str().reverse()).unwrap();
+                        } else {
+                            writeln!(out, "{}", line).unwrap();
+                        }
+                        lines.push(line);
+                    }
+                    event => self.handle_event(&name, event),
+                },
+            }
+        }
+    }
+
+    fn close_session(&mut self, name: &str, err: &dyn Display) {
+        self.sessions.close(name);
+
+        if self.sessions.current_name().is_none() {
+            eprintln!("Connection closed: {}", err);
+            EXIT_CONNECTION_CLOSED.process_exit();
+        }
+
+        writeln!(self.stdout.err(), "Connection to {} closed: {}", name, err).unwrap();
+        self.update_prompt();
+    }
+
+    /// Show the current session in the prompt.
+    fn update_prompt(&mut self) {
+        if let Some(session) = self.sessions.current() {
+            let prompt = self.prompt.render(session);
+            self.stdin.set_prompt(&prompt);
+        }
+    }
+
+    /// Run `status` on the current session, to update the details shown in the prompt.
+    async fn poll_status(&mut self) {
+        let session = match self.sessions.current() {
+            Some(session) => session,
+            None => return,
+        };
+
+        // Errors are ignored, since a closed connection is reported separately
+        if let Some(write) = session.write() {
+            if write.exec_command("status").await.is_ok() {
+                session.polled_at = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Update the details of a session shown in the prompt from a line of `status` output.
+    ///
+    /// Returns true if the line was output from polling, and so shouldn't be shown.
+    fn update_status(&mut self, name: &str, line: &str) -> bool {
+        let session = match self.sessions.get(name) {
+            Some(session) => session,
+            None => return false,
+        };
+
+        if !prompt::update_info(&mut session.info, line) {
+            return false;
+        }
+        let polled = matches!(session.polled_at, Some(at) if at.elapsed() < STATUS_POLL_TIMEOUT);
+
+        if self.sessions.current_name() == Some(name) {
+            self.update_prompt();
+        }
+        polled
+    }
+
+    /// Run each line of some input in order, since continued lines are entered together.
+    async fn run_input(&mut self, input: &str) {
+        for line in input.lines() {
+            self.run_line(line).await;
+        }
+    }
+
+    async fn run_line(&mut self, line: &str) {
         let line = line.trim();
 
+        if let (Some(log_file), Some(name)) = (&mut self.log_file, self.sessions.current_name()) {
+            if let Err(err) = log_file.log_command(name, line) {
+                writeln!(self.stdout.err(), "Can't write to log file: {}", err).unwrap();
+            }
+        }
+        if let (Some(recorder), Some(name)) = (&mut self.recorder, self.sessions.current_name()) {
+            if let Err(err) = recorder.record_command(name, line) {
+                writeln!(self.stdout.err(), "Can't write to recording: {}", err).unwrap();
+            }
+        }
+
+        let line = &self.expand_alias(line);
+
         let result = if let Some(builtin) = line.strip_prefix('!') {
             if builtin == "help" {
+                let err = self.stdout.err();
                 writeln!(
-                    stdout.err(),
-                    r#"{} {}
-{}
-    {}                   View this help listing
-    {}         Enable server console logging
-    {}                   Quit this session
-    {}        Set a ConVar on the server
-    {}     Run a command on the server"#,
+                    err,
+                    "{} {}\n{}",
                     env!("CARGO_PKG_NAME").with(Color::DarkGreen),
                     env!("CARGO_PKG_VERSION"),
-                    "BUILTINS:".with(Color::DarkYellow),
-                    "!help".with(Color::DarkGreen),
-                    "!enable console".with(Color::DarkGreen),
-                    "!quit".with(Color::DarkGreen),
-                    "!set <VAR> <VAL>".with(Color::DarkGreen),
-                    "<COMMAND> [ARGS...]".with(Color::DarkGreen)
+                    "BUILTINS:".with(Color::DarkYellow)
                 )
                 .unwrap();
+
+                let usage_width = BUILTINS.iter().map(|(usage, _)| usage.len()).max().unwrap();
+                for (usage, description) in BUILTINS {
+                    writeln!(
+                        err,
+                        "    {}     {}",
+                        format!("{:<1$}", usage, usage_width).with(Color::DarkGreen),
+                        description
+                    )
+                    .unwrap();
+                }
+                Ok(())
+            } else if let Some(connect_query) = builtin.strip_prefix("connect ") {
+                self.connect_session(connect_query).await;
+                Ok(())
+            } else if builtin == "reconnect" {
+                let name = self.sessions.current_name().unwrap().to_string();
+                match self.sessions.reconnect(&name) {
+                    Ok(()) => writeln!(self.stdout.err(), "Reconnecting to {}...", name).unwrap(),
+                    Err(err) => {
+                        writeln!(self.stdout.err(), "Can't read pass file: {}", err).unwrap()
+                    }
+                }
+                self.update_prompt();
+                Ok(())
+            } else if let Some(name) = builtin.strip_prefix("switch ") {
+                let name = name.trim();
+                if self.sessions.switch(name) {
+                    self.update_prompt();
+                } else {
+                    writeln!(self.stdout.err(), "No session named {}.", name).unwrap();
+                }
+                Ok(())
+            } else if let Some(command) = builtin.strip_prefix("broadcast ") {
+                self.broadcast(command.trim()).await;
+                Ok(())
+            } else if builtin == "sessions" {
+                let current_name = self.sessions.current_name();
+                for session in self.sessions.iter() {
+                    let name = session.name.as_str();
+                    let status = match &session.status {
+                        ConnectionStatus::Connected => String::new(),
+                        status => format!(" ({})", status),
+                    };
+                    if Some(name) == current_name {
+                        writeln!(
+                            self.stdout.err(),
+                            "* {}{}",
+                            name.with(Color::DarkGreen),
+                            status
+                        )
+                        .unwrap();
+                    } else {
+                        writeln!(self.stdout.err(), "  {}{}", name, status).unwrap();
+                    }
+                }
                 Ok(())
             } else if builtin == "enable console" {
-                client_write.enable_console_logs().await
+                match self.connected_session() {
+                    Some(session) => {
+                        session.set_console_logs();
+                        session.write().unwrap().enable_console_logs().await
+                    }
+                    None => Ok(()),
+                }
+            } else if builtin == "filter" {
+                match &self.filter {
+                    Some(filter) => writeln!(self.stdout.err(), "Filter: {}", filter).unwrap(),
+                    None => writeln!(self.stdout.err(), "No filter is set.").unwrap(),
+                }
+                Ok(())
+            } else if let Some(pattern) = builtin.strip_prefix("filter ") {
+                let pattern = pattern.trim();
+                if pattern == "off" {
+                    self.filter = None;
+                } else {
+                    match Filter::parse(pattern) {
+                        Ok(filter) => self.filter = Some(filter),
+                        Err(err) => writeln!(self.stdout.err(), "Invalid filter: {}", err).unwrap(),
+                    }
+                }
+                Ok(())
+            } else if builtin == "alias" {
+                for (name, command) in &self.config.aliases {
+                    writeln!(self.stdout.err(), "{} = {}", name, command).unwrap();
+                }
+                Ok(())
+            } else if let Some(alias_query) = builtin.strip_prefix("alias ") {
+                let alias_query = alias_query.trim();
+                match alias_query.split_once(' ') {
+                    Some((name, command)) => {
+                        self.config
+                            .aliases
+                            .insert(name.to_string(), command.trim().to_string());
+                    }
+                    None => match self.config.aliases.get(alias_query) {
+                        Some(command) => {
+                            writeln!(self.stdout.err(), "{} = {}", alias_query, command).unwrap()
+                        }
+                        None => {
+                            writeln!(self.stdout.err(), "No alias named {}.", alias_query).unwrap()
+                        }
+                    },
+                }
+                Ok(())
+            } else if let Some(command) = builtin.strip_prefix("page ") {
+                self.page_command(command.trim()).await
+            } else if builtin == "status" {
+                self.show_status().await
+            } else if builtin == "players" {
+                self.list_players().await
+            } else if let Some(query) = builtin.strip_prefix("kick ") {
+                self.act_on_player(query.trim(), PlayerAction::Kick).await
+            } else if let Some(query) = builtin.strip_prefix("ban ") {
+                self.act_on_player(query.trim(), PlayerAction::Ban).await
+            } else if let Some(query) = builtin.strip_prefix("map ") {
+                self.change_map(query.trim()).await
             } else if builtin == "quit" {
                 eprintln!();
-                proc_exit::Code::SUCCESS.process_exit();
+                Code::SUCCESS.process_exit();
             } else if let Some(set_query) = builtin.strip_prefix("set ") {
                 match set_query.find(' ') {
                     Some(separator_index) => {
                         let var = set_query[..separator_index].trim();
                         let val = set_query[separator_index + 1..].trim();
-                        client_write.set_value(var, val).await
+                        match self.connected_session() {
+                            Some(session) => session.write().unwrap().set_value(var, val).await,
+                            None => Ok(()),
+                        }
                     }
                     None => {
-                        writeln!(stdout.err(), "Usage: !set <VAR> <VAL>").unwrap();
+                        writeln!(self.stdout.err(), "Usage: !set <VAR> <VAL>").unwrap();
                         Ok(())
                     }
                 }
             } else {
-                writeln!(stdout.err(), "Unknown builtin.").unwrap();
+                writeln!(self.stdout.err(), "Unknown builtin.").unwrap();
                 Ok(())
             }
+        } else if !self.confirm(line).await {
+            Ok(())
+        } else if self.batch || self.show_rtt || self.quiet {
+            self.run_batched(line).await
         } else {
-            client_write.exec_command(line).await
+            match self.connected_session() {
+                Some(session) => session.write().unwrap().exec_command(line).await,
+                None => Ok(()),
+            }
         };
 
         if let Err(err) = result {
-            writeln!(stdout.err(), "An error occurred: {}", err).unwrap();
+            writeln!(self.stdout.err(), "An error occurred: {}", err).unwrap();
+            self.command_failed = true;
+        }
+
+        if self.command_failed && self.fail_fast {
+            eprintln!("Exiting because a command failed.");
+            EXIT_COMMAND_FAILED.process_exit();
+        }
+    }
+
+    /// Exit once there are no more commands to run, reporting whether any failed.
+    fn finish(&self) -> ! {
+        if self.command_failed {
+            EXIT_COMMAND_FAILED.process_exit();
+        } else {
+            Code::SUCCESS.process_exit();
+        }
+    }
+
+    /// Run a command on every session, and show which sessions it couldn't be sent to.
+    async fn broadcast(&mut self, command: &str) {
+        if !self.confirm(command).await {
+            return;
+        }
+
+        let mut sent = 0;
+        for session in self.sessions.iter_mut() {
+            let result = match session.write() {
+                Some(write) => write
+                    .exec_command(command)
+                    .await
+                    .map_err(|err| err.to_string()),
+                None => Err(session.status.to_string()),
+            };
+
+            match result {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    self.command_failed = true;
+                    writeln!(self.stdout.err(), "{}: {}", session.name, err).unwrap();
+                }
+            }
+        }
+        writeln!(
+            self.stdout.err(),
+            "Sent to {} of {} sessions.",
+            sent,
+            self.sessions.len()
+        )
+        .unwrap();
+    }
+
+    fn show_interrupt_hint(&mut self) {
+        writeln!(
+            self.stdout.err(),
+            "Press Ctrl+C again or run !quit to exit."
+        )
+        .unwrap();
+    }
+
+    /// Run a command on the current session and show its output once it stops arriving, so it
+    /// doesn't get mixed up with the output of the next command.
+    async fn run_batched(&mut self, command: &str) -> northstar_rcon_client::Result<()> {
+        let name = match self.sessions.current_name() {
+            Some(name) => name.to_string(),
+            None => return Ok(()),
+        };
+        let (mut lines, round_trip) = match self.capture_command_timed(command).await? {
+            Some(captured) => captured,
+            None => return Ok(()),
+        };
+        if let Some(filter) = &self.filter {
+            lines.retain(|line| filter.allows(line));
+        }
+
+        for line in lines {
+            self.show_log(&name, line);
+        }
+
+        if self.show_rtt {
+            let message = match round_trip {
+                Some(round_trip) => {
+                    let ms = round_trip.as_secs_f64() * 1000.;
+                    format!("Echoed after {:.1} ms (round trip)", ms)
+                }
+                None => "The command wasn't echoed back.".to_string(),
+            };
+            if self.color {
+                writeln!(self.stdout.err(), "{}", message.dim()).unwrap();
+            } else {
+                writeln!(self.stdout.err(), "{}", message).unwrap();
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a command on the current session and show the output that comes back in `$PAGER`.
+    async fn page_command(&mut self, command: &str) -> northstar_rcon_client::Result<()> {
+        let mut lines = match self.capture_command(command).await? {
+            Some(lines) => lines,
+            None => return Ok(()),
+        };
+        if let Some(filter) = &self.filter {
+            lines.retain(|line| filter.allows(line));
+        }
+
+        if !self.stdin.is_interactive() {
+            for line in lines {
+                writeln!(self.stdout.out(), "{}", line).unwrap();
+            }
+            return Ok(());
+        }
+
+        if let Err(err) = self.stdin.suspend(|| run_pager(&lines)) {
+            writeln!(self.stdout.err(), "Can't open pager: {}", err).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Run `status` on the current session and show a summary of the server above its output.
+    async fn show_status(&mut self) -> northstar_rcon_client::Result<()> {
+        let mut lines = match self.capture_command("status").await? {
+            Some(lines) => lines,
+            None => return Ok(()),
+        };
+
+        let mut info = ServerInfo::default();
+        for line in &lines {
+            info.update(line);
+        }
+        let players = prompt::format_players(&info);
+        let map = info
+            .map
+            .map(|map| match northstar_rcon_client::map_display_name(&map) {
+                Some(display_name) => format!("{} ({})", display_name, map),
+                None => map,
+            });
+
+        let out = self.stdout.out();
+        for (label, value) in [
+            ("Hostname", info.hostname),
+            ("Map", map),
+            ("Players", players),
+        ] {
+            writeln!(
+                out,
+                "{:<10}{}",
+                format!("{}:", label),
+                value.as_deref().unwrap_or("?")
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+
+        if let Some(filter) = &self.filter {
+            lines.retain(|line| filter.allows(line));
+        }
+        for line in lines {
+            writeln!(self.stdout.out(), "{}", line).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Run `status` on the current session and show the players in a table.
+    async fn list_players(&mut self) -> northstar_rcon_client::Result<()> {
+        let players = match self.capture_players().await? {
+            Some(players) => players,
+            None => return Ok(()),
+        };
+
+        if players.is_empty() {
+            writeln!(self.stdout.err(), "No players are connected.").unwrap();
+            return Ok(());
+        }
+
+        for row in players::format_table(&players) {
+            writeln!(self.stdout.out(), "{}", row).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Change to the map a name refers to, warning before changing to a map that isn't known.
+    async fn change_map(&mut self, query: &str) -> northstar_rcon_client::Result<()> {
+        let session = match self.connected_session() {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+
+        let map = match maps::find(query, session.info.map.as_deref()) {
+            MapMatch::Exact(map) => map,
+            MapMatch::Partial(maps) if maps.len() == 1 => {
+                writeln!(self.stdout.err(), "Changing map to {}.", maps[0]).unwrap();
+                maps[0].clone()
+            }
+            MapMatch::Partial(maps) if maps.is_empty() => {
+                let question = format!("{} isn't a known map. Change to it anyway? [y/N] ", query);
+                if self.stdin.is_interactive() {
+                    match self.ask(&question).await {
+                        Some(answer) if answer.trim().eq_ignore_ascii_case("y") => {}
+                        _ => return Ok(()),
+                    }
+                } else {
+                    writeln!(self.stdout.err(), "Warning: {} isn't a known map.", query).unwrap();
+                }
+                query.to_string()
+            }
+            MapMatch::Partial(maps) => {
+                self.command_failed = true;
+                writeln!(
+                    self.stdout.err(),
+                    "{} matches more than one map: {}",
+                    query,
+                    maps.join(", ")
+                )
+                .unwrap();
+                return Ok(());
+            }
+        };
+
+        match self.connected_session() {
+            Some(session) => {
+                session
+                    .write()
+                    .unwrap()
+                    .exec_command(&format!("map {}", map))
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Kick or ban the player a name or ID refers to, asking which player was meant if it isn't
+    /// clear.
+    async fn act_on_player(
+        &mut self,
+        query: &str,
+        action: PlayerAction,
+    ) -> northstar_rcon_client::Result<()> {
+        let players = match self.capture_players().await? {
+            Some(players) => players,
+            None => return Ok(()),
+        };
+
+        let player = match players::find(&players, query) {
+            PlayerMatch::Exact(player) => {
+                if !self.confirm(&action.command(player)).await {
+                    return Ok(());
+                }
+                player.clone()
+            }
+            PlayerMatch::Partial(matches) => {
+                match self.choose_player(query, &matches, action).await {
+                    Some(player) => player,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let session = match self.connected_session() {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+        session
+            .write()
+            .unwrap()
+            .exec_command(&action.command(&player))
+            .await?;
+
+        // Check the player list again to see whether it worked
+        let players = match self.capture_players().await? {
+            Some(players) => players,
+            None => return Ok(()),
+        };
+        if players.iter().any(|other| other.uid == player.uid) {
+            self.command_failed = true;
+            writeln!(
+                self.stdout.err(),
+                "Tried to {} {}, but they're still connected.",
+                action.verb(),
+                player
+            )
+            .unwrap();
+        } else {
+            writeln!(self.stdout.err(), "{} is no longer connected.", player).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Ask which player a partial name was meant to match.
+    async fn choose_player(
+        &mut self,
+        query: &str,
+        matches: &[&Player],
+        action: PlayerAction,
+    ) -> Option<Player> {
+        if matches.is_empty() {
+            self.command_failed = true;
+            writeln!(self.stdout.err(), "No player matches {}.", query).unwrap();
+            return None;
+        }
+        if !self.stdin.is_interactive() {
+            self.command_failed = true;
+            writeln!(
+                self.stdout.err(),
+                "{} doesn't exactly match a player. Use their full name or UID instead.",
+                query
+            )
+            .unwrap();
+            return None;
+        }
+
+        if let [player] = matches {
+            let question = format!("{} {}? [y/N] ", capitalize(action.verb()), player);
+            return match self.ask(&question).await {
+                Some(answer) if answer.trim().eq_ignore_ascii_case("y") => Some((*player).clone()),
+                _ => None,
+            };
+        }
+
+        for (index, player) in matches.iter().enumerate() {
+            writeln!(self.stdout.err(), "{}. {}", index + 1, player).unwrap();
+        }
+        let question = format!("Which player? [1-{}] ", matches.len());
+        let answer = self.ask(&question).await?;
+        match answer.trim().parse::<usize>() {
+            Ok(number) if (1..=matches.len()).contains(&number) => {
+                Some(matches[number - 1].clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Ask whether to send a command line if it runs a dangerous command, returning whether it
+    /// should be sent.
+    async fn confirm(&mut self, line: &str) -> bool {
+        let request = Request::ExecCommand { cmd: line };
+        let name = match self.confirm.as_ref().map(|confirm| confirm.check(&request)) {
+            Some(Err(northstar_rcon_client::Error::PermissionDenied(name))) => name,
+            _ => return true,
+        };
+
+        let question = format!("Really run {}? [y/N] ", name);
+        match self.ask(&question).await {
+            Some(answer) if answer.trim().eq_ignore_ascii_case("y") => true,
+            _ => {
+                writeln!(self.stdout.err(), "Not sent.").unwrap();
+                false
+            }
+        }
+    }
+
+    /// Show a question in place of the prompt and wait for an answer, while handling session
+    /// events. Returns `None` if Ctrl+C was pressed.
+    async fn ask(&mut self, question: &str) -> Option<String> {
+        self.stdin.set_prompt(question);
+        let answer = loop {
+            select! {
+                input = self.stdin.read_line() => match input {
+                    Some(Input::Line(line)) => break Some(line),
+                    _ => {
+                        self.stdin.clear_interrupt();
+                        break None;
+                    }
+                },
+                Some((name, event)) = self.events.recv() => self.handle_event(&name, event),
+            }
+        };
+        self.update_prompt();
+        answer
+    }
+
+    /// Run `status` on the current session and find the players it lists.
+    async fn capture_players(&mut self) -> northstar_rcon_client::Result<Option<Vec<Player>>> {
+        let lines = match self.capture_command("status").await? {
+            Some(lines) => lines,
+            None => return Ok(None),
+        };
+        Ok(Some(
+            lines
+                .iter()
+                .filter_map(|line| Player::parse(line))
+                .collect(),
+        ))
+    }
+
+    /// Run a command on the current session and collect the output that comes back, instead of
+    /// showing it. Returns `None` if the session isn't connected or collecting was cancelled.
+    async fn capture_command(
+        &mut self,
+        command: &str,
+    ) -> northstar_rcon_client::Result<Option<Vec<String>>> {
+        Ok(self
+            .capture_command_timed(command)
+            .await?
+            .map(|(lines, _)| lines))
+    }
+
+    /// Like [`Cli::capture_command`], but also returns how long it took for the server to echo
+    /// the command back, like `] status`, if it did.
+    async fn capture_command_timed(
+        &mut self,
+        command: &str,
+    ) -> northstar_rcon_client::Result<Option<(Vec<String>, Option<Duration>)>> {
+        let session = match self.connected_session() {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+        let name = session.name.clone();
+
+        // Output from here on is the command's, even if it looks like a prompt status poll's
+        session.polled_at = None;
+        let sent_at = Instant::now();
+        session.write().unwrap().exec_command(command).await?;
+
+        // Collect output until it stops arriving, handling events from other sessions as usual
+        let mut lines = Vec::new();
+        let mut round_trip = None;
+        let mut echo = CommandEcho::new();
+        echo.sent(command);
+        let deadline = Instant::now() + CAPTURE_MAX_TIME;
+        let interactive = self.stdin.is_interactive();
+        loop {
+            let wait = CAPTURE_QUIET_TIME.min(deadline.saturating_duration_since(Instant::now()));
+            select! {
+                event = tokio::time::timeout(wait, self.events.recv()) => match event {
+                    Ok(Some((event_name, SessionEvent::Log(log)))) if event_name == name => {
+                        if round_trip.is_none() && echo.observe(&log).is_some() {
+                            round_trip = Some(sent_at.elapsed());
+                        }
+                        self.update_status(&name, &log);
+                        self.record_log(&name, &log);
+                        lines.push(log);
+                    }
+                    Ok(Some((event_name, event))) => self.handle_event(&event_name, event),
+                    Ok(None) | Err(_) => break,
+                },
+
+                // Ctrl+C cancels, and entering a line shows what's been collected so far
+                Some(input) = self.stdin.read_line(), if interactive => match input {
+                    Input::Line(line) => {
+                        self.pending_line = Some(line);
+                        break;
+                    }
+                    Input::Interrupted => {
+                        self.stdin.clear_interrupt();
+                        writeln!(self.stdout.err(), "Cancelled.").unwrap();
+                        return Ok(None);
+                    }
+                },
+            }
+        }
+
+        Ok(Some((lines, round_trip)))
+    }
+
+    // Replace an alias at the start of a line with its command, keeping any arguments after it
+    fn expand_alias(&self, line: &str) -> String {
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+        match self.config.aliases.get(name) {
+            Some(command) if args.is_empty() => command.clone(),
+            Some(command) => format!("{} {}", command, args),
+            None => line.to_string(),
+        }
+    }
+
+    // Get the current session if it's connected, otherwise explain why commands can't be sent
+    fn connected_session(&mut self) -> Option<&mut Session> {
+        // The process exits when the last session closes, so there is always a current session
+        let session = self.sessions.current().unwrap();
+        if session.write().is_some() {
+            Some(session)
+        } else {
+            self.command_failed = true;
+            writeln!(
+                self.stdout.err(),
+                "{} is disconnected. Use !reconnect to connect again.",
+                session.name
+            )
+            .unwrap();
+            None
+        }
+    }
+
+    async fn connect_session(&mut self, query: &str) {
+        let mut parts = query.split_whitespace();
+        let (profile_or_addr, name) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(profile_or_addr), name, None) => (profile_or_addr, name),
+            _ => {
+                writeln!(self.stdout.err(), "Usage: !connect <PROFILE> [NAME]").unwrap();
+                return;
+            }
+        };
+
+        let mut target = match Target::resolve(&self.config, profile_or_addr) {
+            Ok(target) => target,
+            Err(err) => {
+                writeln!(
+                    self.stdout.err(),
+                    "Invalid address {}: {}",
+                    profile_or_addr,
+                    err
+                )
+                .unwrap();
+                return;
+            }
+        };
+        if let Some(name) = name {
+            target.name = name.to_string();
+        }
+
+        if self.sessions.contains(&target.name) {
+            writeln!(
+                self.stdout.err(),
+                "A session named {} is already open.",
+                target.name
+            )
+            .unwrap();
+            return;
+        }
+
+        let keyring_pass = match &target.pass_file {
+            Some(_) => None,
+            None if self.use_keyring => match load_keyring_pass(&target.profile) {
+                Ok(pass) => pass,
+                Err(err) => {
+                    writeln!(
+                        self.stdout.err(),
+                        "Can't read password from keyring: {}",
+                        err
+                    )
+                    .unwrap();
+                    None
+                }
+            },
+            None => None,
+        };
+        let mut pass_source = match (&target.pass_file, keyring_pass) {
+            (Some(pass_file), _) => PassSource::File(pass_file.clone()),
+            (None, Some(_)) => PassSource::Keyring(target.profile.clone()),
+            (None, None) => match self.prompt_session_password(&target) {
+                Some(pass_source) => pass_source,
+                None => return,
+            },
+        };
+
+        let settings = self.sessions.settings();
+        let mut client = match connect_within(target.addr, self.connect_timeout, settings).await {
+            Ok(client) => client,
+            Err(err) => {
+                writeln!(self.stdout.err(), "Connection failed: {}", err).unwrap();
+                return;
+            }
+        };
+
+        loop {
+            let pass = match pass_source.read() {
+                Ok(pass) => pass,
+                Err(err) => {
+                    writeln!(self.stdout.err(), "Can't read password: {}", err).unwrap();
+                    return;
+                }
+            };
+
+            match client.authenticate(&pass).await {
+                Ok((read, write)) => {
+                    if let (true, PassSource::Entered(pass)) = (self.use_keyring, &pass_source) {
+                        if let Err(err) = save_keyring_pass(&target.profile, pass) {
+                            writeln!(self.stdout.err(), "Can't save password to keyring: {}", err)
+                                .unwrap();
+                        }
+                    }
+
+                    self.sessions
+                        .open(target.name.clone(), target.addr, pass_source, read, write);
+                    self.update_prompt();
+                    return;
+                }
+
+                // A password saved in the keyring may be out of date, so ask for the new one
+                Err((new_client, AuthError::InvalidPassword { .. }))
+                    if matches!(pass_source, PassSource::Keyring(_)) =>
+                {
+                    writeln!(
+                        self.stdout.err(),
+                        "The password saved in the keyring is wrong."
+                    )
+                    .unwrap();
+                    pass_source = match self.prompt_session_password(&target) {
+                        Some(pass_source) => pass_source,
+                        None => return,
+                    };
+                    client = new_client;
+                }
+
+                Err((_, err)) => {
+                    writeln!(
+                        self.stdout.err(),
+                        "Authentication failed: {}",
+                        CliAuthError(err)
+                    )
+                    .unwrap();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn prompt_session_password(&mut self, target: &Target) -> Option<PassSource> {
+        match self
+            .stdin
+            .prompt_password(&format!("{}'s password: ", target.name))
+        {
+            Ok(pass) => Some(PassSource::Entered(pass)),
+            Err(err) => {
+                writeln!(self.stdout.err(), "Can't read password: {}", err).unwrap();
+                None
+            }
         }
     }
 }
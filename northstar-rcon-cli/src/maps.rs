@@ -0,0 +1,27 @@
+use northstar_rcon_client::maps;
+
+/// How a map name typed by the user matched the known maps.
+pub enum MapMatch {
+    /// The query was the name or in-game name of a map.
+    Exact(String),
+
+    /// Maps whose names or in-game names contain the query, ignoring case. Could be empty.
+    Partial(Vec<String>),
+}
+
+/// Find the maps a name refers to, out of the known maps and any others the server has reported.
+pub fn find(query: &str, reported: Option<&str>) -> MapMatch {
+    let query = query.to_lowercase();
+    let mut partial = Vec::new();
+    for map in maps(reported) {
+        let display_name = map.display_name.unwrap_or(&map.name).to_lowercase();
+        if map.name == query || display_name == query {
+            return MapMatch::Exact(map.name);
+        }
+        if map.name.contains(&query) || display_name.contains(&query) {
+            partial.push(map.name);
+        }
+    }
+
+    MapMatch::Partial(partial)
+}
@@ -0,0 +1,133 @@
+use northstar_rcon_client::strip_tags;
+use std::fmt::{Display, Formatter};
+
+/// A player listed in the output of `status`.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub userid: u32,
+    pub name: String,
+    pub uid: String,
+    pub connected: String,
+    pub ping: String,
+}
+
+impl Player {
+    /// Parse a line of the player list, like `#  2 "Pilot" 1000123456 05:12 45 0 active`.
+    ///
+    /// Returns `None` for other lines, including the `# userid name ...` header.
+    pub fn parse(line: &str) -> Option<Player> {
+        let rest = strip_tags(line).strip_prefix('#')?.trim_start();
+
+        let (userid, rest) = rest.split_once(char::is_whitespace)?;
+        let userid = userid.parse().ok()?;
+
+        // Names are quoted and can contain spaces, so find the last quote rather than splitting
+        let rest = rest.trim_start().strip_prefix('"')?;
+        let name_end = rest.rfind('"')?;
+        let name = rest[..name_end].to_string();
+
+        let mut fields = rest[name_end + 1..].split_whitespace();
+        Some(Player {
+            userid,
+            name,
+            uid: fields.next()?.to_string(),
+            connected: fields.next()?.to_string(),
+            ping: fields.next()?.to_string(),
+        })
+    }
+}
+
+impl Display for Player {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.uid)
+    }
+}
+
+/// Format players as a table with aligned columns, one line per row.
+pub fn format_table(players: &[Player]) -> Vec<String> {
+    let rows: Vec<[String; 5]> = players
+        .iter()
+        .map(|player| {
+            [
+                player.userid.to_string(),
+                player.name.clone(),
+                player.uid.clone(),
+                player.ping.clone(),
+                player.connected.clone(),
+            ]
+        })
+        .collect();
+
+    let header = ["ID", "NAME", "UID", "PING", "CONNECTED"].map(str::to_string);
+    let mut widths = header.clone().map(|cell| cell.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<1$}", cell, width))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// How a name or ID typed by the user matched the players on a server.
+pub enum PlayerMatch<'a> {
+    /// The query was the user ID, UID or full name of a player.
+    Exact(&'a Player),
+
+    /// Players whose names contain the query, ignoring case. Could be empty.
+    Partial(Vec<&'a Player>),
+}
+
+/// Find the players a name or ID refers to.
+pub fn find<'a>(players: &'a [Player], query: &str) -> PlayerMatch<'a> {
+    let exact = players.iter().find(|player| {
+        player.userid.to_string() == query
+            || player.uid == query
+            || player.name.eq_ignore_ascii_case(query)
+    });
+    if let Some(player) = exact {
+        return PlayerMatch::Exact(player);
+    }
+
+    let query = query.to_lowercase();
+    PlayerMatch::Partial(
+        players
+            .iter()
+            .filter(|player| player.name.to_lowercase().contains(&query))
+            .collect(),
+    )
+}
+
+/// Something that can be done to a player with a builtin.
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerAction {
+    Kick,
+    Ban,
+}
+
+impl PlayerAction {
+    pub fn verb(self) -> &'static str {
+        match self {
+            PlayerAction::Kick => "kick",
+            PlayerAction::Ban => "ban",
+        }
+    }
+
+    /// The server command that does the action to a player.
+    pub fn command(self, player: &Player) -> String {
+        match self {
+            PlayerAction::Kick => format!("kickid {}", player.userid),
+            PlayerAction::Ban => format!("ban {}", player.uid),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crossterm::cursor::MoveToColumn;
+use crossterm::execute;
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long a stage has to take before the spinner is shown, so quick connections don't flicker.
+const SHOW_AFTER: Duration = Duration::from_millis(200);
+
+const FRAME_TIME: Duration = Duration::from_millis(100);
+const FRAMES: &[char] = &['|', '/', '-', '\\'];
+
+/// A spinner on stderr showing which stage of connecting is happening and how long it's taken.
+///
+/// It's drawn from its own thread, so it keeps moving while the main thread is blocked on
+/// something like resolving an address. [`Progress::finish`] must be called before printing
+/// anything else to stderr.
+pub struct Progress {
+    stage: Arc<Mutex<(String, Instant)>>,
+    running: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+impl Progress {
+    /// Start showing a stage, if `visible` is set.
+    pub fn start(stage: impl Into<String>, visible: bool) -> Self {
+        let stage = Arc::new(Mutex::new((stage.into(), Instant::now())));
+        let running = visible.then(|| {
+            let (stop_tx, stop_rx) = mpsc::channel();
+            let thread_stage = stage.clone();
+            let thread = std::thread::spawn(move || spin(&thread_stage, &stop_rx));
+            (stop_tx, thread)
+        });
+
+        Progress { stage, running }
+    }
+
+    /// Move on to the next stage, restarting the elapsed time.
+    pub fn stage(&mut self, stage: impl Into<String>) {
+        *self.stage.lock().unwrap() = (stage.into(), Instant::now());
+    }
+
+    /// Stop showing the spinner and clear it.
+    pub fn finish(&mut self) {
+        if let Some((stop_tx, thread)) = self.running.take() {
+            let _ = stop_tx.send(());
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn spin(stage: &Mutex<(String, Instant)>, stop: &mpsc::Receiver<()>) {
+    let mut stderr = std::io::stderr();
+    let mut shown = false;
+
+    for frame in FRAMES.iter().cycle() {
+        match stop.recv_timeout(FRAME_TIME) {
+            Err(RecvTimeoutError::Timeout) => {}
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let (message, started) = stage.lock().unwrap().clone();
+        let elapsed = started.elapsed();
+        if elapsed < SHOW_AFTER {
+            continue;
+        }
+
+        shown = true;
+        let _ = execute!(
+            stderr,
+            MoveToColumn(0),
+            Clear(ClearType::CurrentLine),
+            Print(format!(
+                "{} {}... {:.1}s",
+                frame,
+                message,
+                elapsed.as_secs_f64()
+            ))
+        );
+    }
+
+    if shown {
+        let _ = execute!(stderr, MoveToColumn(0), Clear(ClearType::CurrentLine));
+    }
+}
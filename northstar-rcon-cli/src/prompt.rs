@@ -0,0 +1,66 @@
+use crate::session::{ConnectionStatus, Session};
+use northstar_rcon_client::{strip_tags, ServerInfo};
+
+/// The prompt used when no template is configured.
+pub const DEFAULT_TEMPLATE: &str = "{name}{status}> ";
+
+/// A prompt with placeholders that are filled in for the current session.
+///
+/// `{name}` is replaced with the session name, and `{status}` with nothing while it's connected or
+/// something like ` (reconnecting)` while it isn't. `{map}` and `{players}` are replaced with
+/// details about the server found by polling its `status` command.
+pub struct PromptTemplate(String);
+
+impl PromptTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        PromptTemplate(template.into())
+    }
+
+    /// Whether the template shows details that need the server's status to be polled.
+    pub fn needs_status(&self) -> bool {
+        self.0.contains("{map}") || self.0.contains("{players}")
+    }
+
+    pub fn render(&self, session: &Session) -> String {
+        let status = match &session.status {
+            ConnectionStatus::Connected => String::new(),
+            status => format!(" ({})", status),
+        };
+
+        self.0
+            .replace("{name}", &session.name)
+            .replace("{status}", &status)
+            .replace("{map}", session.info.map.as_deref().unwrap_or("?"))
+            .replace(
+                "{players}",
+                format_players(&session.info).as_deref().unwrap_or("?"),
+            )
+    }
+}
+
+// Fields printed by `status` before the player list, which are recognized but not shown
+const OTHER_STATUS_FIELDS: &[&str] = &["version", "udp/ip", "os", "type"];
+
+/// Update the details about a server from a line of `status` output. Returns whether the line
+/// looked like part of the output.
+pub fn update_info(info: &mut ServerInfo, line: &str) -> bool {
+    if info.update(line) {
+        return true;
+    }
+
+    let line = strip_tags(line);
+    line.starts_with('#')
+        || line
+            .split_once(':')
+            .is_some_and(|(field, _)| OTHER_STATUS_FIELDS.contains(&field.trim()))
+}
+
+/// Show the player count like `3/16`, with whichever half the server didn't print as `?`.
+pub fn format_players(info: &ServerInfo) -> Option<String> {
+    match (info.players, info.max_players) {
+        (Some(players), Some(max)) => Some(format!("{}/{}", players, max)),
+        (Some(players), None) => Some(format!("{}/?", players)),
+        (None, Some(max)) => Some(format!("?/{}", max)),
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,127 @@
+use crate::highlight;
+use crossterm::style::Stylize;
+use crossterm::tty::IsTty;
+use proc_exit::Code;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+/// The first line of every recording, so other files aren't replayed by mistake.
+const HEADER: &str = "# nsrcon recording v1";
+
+/// Records the commands typed into each session and the console log lines that come back, with
+/// when they happened, so the session can be replayed later.
+///
+/// Recordings are text files with one event per line, like `1520\tserver\t<\t[info] Hello`.
+/// The fields are the milliseconds since recording started, the session name, `>` for a command
+/// or `<` for a log line, and the text.
+pub struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", HEADER)?;
+        file.flush()?;
+
+        Ok(Recorder {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record_command(&mut self, session: &str, command: &str) -> std::io::Result<()> {
+        if command.is_empty() {
+            return Ok(());
+        }
+        self.write(session, '>', command)
+    }
+
+    pub fn record_line(&mut self, session: &str, line: &str) -> std::io::Result<()> {
+        self.write(session, '<', line.trim_end_matches(['\r', '\n']))
+    }
+
+    fn write(&mut self, session: &str, kind: char, text: &str) -> std::io::Result<()> {
+        writeln!(
+            self.file,
+            "{}\t{}\t{}\t{}",
+            self.started.elapsed().as_millis(),
+            session,
+            kind,
+            text
+        )?;
+
+        // Flushed after every line so the recording is complete however the client exits
+        self.file.flush()
+    }
+}
+
+/// Print a recording as it happened, waiting between events for as long as they originally took
+/// divided by `speed`, and exit.
+pub async fn replay(path: &str, speed: f64) -> ! {
+    let file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(err) => {
+            eprintln!("Can't open recording: {}", err);
+            Code::IO_ERR.process_exit();
+        }
+    };
+    let color =
+        std::env::var("NO_COLOR").unwrap_or_default().is_empty() && std::io::stdout().is_tty();
+
+    let started = Instant::now();
+    let mut lines = file.lines();
+    match lines.next() {
+        Some(Ok(header)) if header == HEADER => {}
+        Some(Err(err)) => {
+            eprintln!("Can't read recording: {}", err);
+            Code::IO_ERR.process_exit();
+        }
+        _ => {
+            eprintln!("{} isn't a recording made with `record`.", path);
+            Code::DATA_ERR.process_exit();
+        }
+    }
+
+    for (index, line) in lines.enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("Can't read recording: {}", err);
+                Code::IO_ERR.process_exit();
+            }
+        };
+
+        let mut fields = line.splitn(4, '\t');
+        let event = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(millis), Some(session), Some(kind), Some(text)) => millis
+                .parse::<u64>()
+                .ok()
+                .map(|millis| (millis, session, kind, text)),
+            _ => None,
+        };
+        let (millis, session, kind, text) = match event {
+            Some(event) => event,
+            None => {
+                eprintln!("Invalid event on line {} of the recording.", index + 2);
+                Code::DATA_ERR.process_exit();
+            }
+        };
+
+        let at = Duration::from_millis(millis).div_f64(speed);
+        tokio::time::sleep(at.saturating_sub(started.elapsed())).await;
+
+        let mut out = std::io::stdout();
+        match (kind, color) {
+            (">", true) => writeln!(out, "{}", format!("{}> {}", session, text).bold()),
+            (">", false) => writeln!(out, "{}> {}", session, text),
+            (_, true) => writeln!(out, "{}", highlight::highlight(text)),
+            (_, false) => writeln!(out, "{}", text),
+        }
+        .unwrap();
+    }
+
+    Code::SUCCESS.process_exit();
+}
@@ -0,0 +1,196 @@
+use crate::progress::Progress;
+use crate::{connect_within, ClientSettings};
+use crossterm::tty::IsTty;
+use northstar_rcon_client::AuthError;
+use proc_exit::Code;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::{JoinError, JoinSet};
+
+/// How long to wait for each address when no connect timeout is passed.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How many addresses are probed at the same time.
+const MAX_PROBES: usize = 256;
+
+/// The smallest prefix that can be scanned, so a typo doesn't start scanning millions of addresses.
+const MIN_PREFIX: u8 = 16;
+
+// Sent to find out whether something is an RCON server. It's very unlikely to be the password, so
+// it doesn't authenticate.
+const PROBE_PASS: &str = "northstar-rcon-scan";
+
+/// How an RCON server answered the probe.
+enum Answer {
+    InvalidPassword,
+    Banned,
+    NoPassword,
+}
+
+/// Look for RCON servers on a network and print their addresses with how long they took to
+/// answer, then exit.
+///
+/// Each address and port is sent a single authentication request with a password that won't
+/// work. Anything that answers it like an RCON server is listed. Without a network, the /24 that
+/// this machine is on is scanned.
+pub async fn run(
+    network: Option<&str>,
+    ports: RangeInclusive<u16>,
+    timeout: Option<Duration>,
+    settings: &ClientSettings,
+) -> ! {
+    let (ip, prefix) = match network {
+        Some(network) => match parse_network(network) {
+            Ok(network) => network,
+            Err(err) => {
+                eprintln!("Invalid network {}: {}", network, err);
+                Code::USAGE_ERR.process_exit();
+            }
+        },
+        None => match local_network() {
+            Some(network) => network,
+            None => {
+                eprintln!(
+                    "Can't tell which network this machine is on, pass one like 10.0.0.0/24."
+                );
+                Code::USAGE_ERR.process_exit();
+            }
+        },
+    };
+    let ip = Ipv4Addr::from(u32::from(ip) & mask(prefix));
+    let timeout = timeout.unwrap_or(DEFAULT_TIMEOUT);
+
+    let mut progress = Progress::start(
+        format!("Scanning {}/{}", ip, prefix),
+        std::io::stderr().is_tty(),
+    );
+    let settings = Arc::new(settings.clone());
+    let mut found = Vec::new();
+    let mut probes = JoinSet::new();
+    for host in hosts(ip, prefix) {
+        for port in ports.clone() {
+            // Wait for a probe to finish before starting another once enough are running, so
+            // there's never a task for every address at once
+            if probes.len() == MAX_PROBES {
+                if let Some(probe) = probes.join_next().await {
+                    keep_answer(&mut found, probe);
+                }
+            }
+
+            let addr = SocketAddr::V4(SocketAddrV4::new(host, port));
+            let settings = settings.clone();
+            probes.spawn(async move { (addr, probe(addr, timeout, &settings).await) });
+        }
+    }
+    while let Some(probe) = probes.join_next().await {
+        keep_answer(&mut found, probe);
+    }
+    progress.finish();
+
+    if found.is_empty() {
+        eprintln!("No RCON servers found on {}/{}.", ip, prefix);
+        Code::SUCCESS.process_exit();
+    }
+
+    found.sort_by_key(|(addr, _)| *addr);
+    for (addr, (answer, latency)) in found {
+        let note = match answer {
+            Answer::InvalidPassword => "",
+            Answer::Banned => " (banned)",
+            Answer::NoPassword => " (no password)",
+        };
+        println!(
+            "{:<21} {:>7.1} ms{}",
+            addr.to_string(),
+            latency.as_secs_f64() * 1000.,
+            note
+        );
+    }
+    Code::SUCCESS.process_exit();
+}
+
+// Add a finished probe to the addresses found, if it was answered
+fn keep_answer(
+    found: &mut Vec<(SocketAddr, (Answer, Duration))>,
+    probe: Result<(SocketAddr, Option<(Answer, Duration)>), JoinError>,
+) {
+    if let Ok((addr, Some(answer))) = probe {
+        found.push((addr, answer));
+    }
+}
+
+/// Send an authentication request to an address, returning how it was answered and how long the
+/// answer took, or `None` if it didn't answer like an RCON server in time.
+async fn probe(
+    addr: SocketAddr,
+    timeout: Duration,
+    settings: &ClientSettings,
+) -> Option<(Answer, Duration)> {
+    let client = connect_within(addr, Some(timeout), settings).await.ok()?;
+
+    let started = Instant::now();
+    let answer = match tokio::time::timeout(timeout, client.authenticate(PROBE_PASS))
+        .await
+        .ok()?
+    {
+        Ok(_) => Answer::NoPassword,
+        Err((_, AuthError::InvalidPassword { .. })) => Answer::InvalidPassword,
+        Err((_, AuthError::Banned { .. })) => Answer::Banned,
+        Err((_, AuthError::Fatal(_))) => return None,
+    };
+    Some((answer, started.elapsed()))
+}
+
+/// Parse a network like `10.0.0.0/24`. A plain address is treated as a /32.
+fn parse_network(network: &str) -> Result<(Ipv4Addr, u8), String> {
+    let (ip, prefix) = network.split_once('/').unwrap_or((network, "32"));
+    let ip: Ipv4Addr = ip.parse().map_err(|_| "not an IPv4 address".to_string())?;
+    let prefix: u8 = match prefix.parse() {
+        Ok(prefix) if prefix <= 32 => prefix,
+        _ => return Err("the prefix must be between 0 and 32".to_string()),
+    };
+    if prefix < MIN_PREFIX {
+        return Err(format!("only /{} and smaller can be scanned", MIN_PREFIX));
+    }
+    Ok((ip, prefix))
+}
+
+/// Parse a port like `37015`, or a range of ports like `37015-37020`.
+pub fn parse_ports(ports: &str) -> Result<RangeInclusive<u16>, String> {
+    let (start, end) = ports.split_once('-').unwrap_or((ports, ports));
+    let start: u16 = start.trim().parse().map_err(|err| format!("{}", err))?;
+    let end: u16 = end.trim().parse().map_err(|err| format!("{}", err))?;
+    if start > end {
+        return Err("the first port must be before the last".to_string());
+    }
+    Ok(start..=end)
+}
+
+/// The /24 that this machine's address is in, found from the address used to reach the internet.
+fn local_network() -> Option<(Ipv4Addr, u8)> {
+    // Connecting a UDP socket picks a route without sending anything
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_unspecified() => Some((ip, 24)),
+        _ => None,
+    }
+}
+
+fn mask(prefix: u8) -> u32 {
+    u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0)
+}
+
+/// The host addresses in a network, leaving out the network and broadcast addresses.
+fn hosts(ip: Ipv4Addr, prefix: u8) -> impl Iterator<Item = Ipv4Addr> {
+    let first = u32::from(ip) & mask(prefix);
+    let last = first | !mask(prefix);
+    let (first, last) = if prefix < 31 {
+        (first + 1, last - 1)
+    } else {
+        (first, last)
+    };
+    (first..=last).map(Ipv4Addr::from)
+}
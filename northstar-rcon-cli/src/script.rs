@@ -0,0 +1,54 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A single line of a script.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Run a command or builtin, the same as if it was typed in.
+    Run(String),
+
+    /// Wait, showing any output that arrives in the meantime.
+    Sleep(Duration),
+}
+
+pub struct ScriptError {
+    line: usize,
+    message: String,
+}
+
+/// Parse a script file.
+///
+/// Each line is a command or builtin. Blank lines and lines starting with `#` are skipped, and
+/// `sleep <ms>` pauses for a number of milliseconds before running the next line.
+pub fn parse(contents: &str) -> Result<Vec<Step>, ScriptError> {
+    let mut steps = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let step = match line.strip_prefix("sleep ") {
+            Some(millis) => match millis.trim().parse() {
+                Ok(millis) => Step::Sleep(Duration::from_millis(millis)),
+                Err(err) => {
+                    return Err(ScriptError {
+                        line: index + 1,
+                        message: format!("invalid sleep duration: {}", err),
+                    })
+                }
+            },
+            None => Step::Run(line.to_string()),
+        };
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
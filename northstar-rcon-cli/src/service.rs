@@ -0,0 +1,41 @@
+use northstar_rcon_client::LogLevel;
+
+/// Prefix a console log line with its priority, like `<4>` for warnings, so journald records it
+/// with the right priority when it's written to standard output.
+pub fn journal_line(line: &str) -> String {
+    let priority = match LogLevel::parse(line) {
+        Some(LogLevel::Trace | LogLevel::Debug) => 7,
+        Some(LogLevel::Info) | None => 6,
+        Some(LogLevel::Warning) => 4,
+        Some(LogLevel::Error) => 3,
+        Some(LogLevel::Critical) => 2,
+    };
+    format!("<{}>{}", priority, line)
+}
+
+/// Tell systemd that the service has started, if it's running as a `Type=notify` service.
+pub fn notify_ready() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::UnixDatagram;
+
+        let path = match std::env::var_os("NOTIFY_SOCKET") {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let socket = UnixDatagram::unbound()?;
+
+        // Sockets starting with `@` are in the abstract namespace
+        #[cfg(target_os = "linux")]
+        if let Some(name) = path.as_bytes().strip_prefix(b"@") {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(b"READY=1", &addr)?;
+            return Ok(());
+        }
+
+        socket.send_to(b"READY=1", path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use crate::ClientSettings;
+use northstar_rcon_client::{
+    ClientRead, ClientWrite, ReconnectError, ReconnectingClient, ServerInfo,
+};
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+/// Where a session gets its password from when reconnecting.
+pub enum PassSource {
+    /// Read the password from a file again, in case it changed.
+    File(String),
+
+    /// Reuse the password that was entered at the prompt.
+    Entered(String),
+
+    /// Read the password saved in the OS keyring for a profile again, in case it changed.
+    Keyring(String),
+}
+
+impl PassSource {
+    pub fn read(&self) -> std::io::Result<String> {
+        match self {
+            PassSource::File(pass_file) => crate::read_pass_file(pass_file),
+            PassSource::Entered(pass) => Ok(pass.clone()),
+            PassSource::Keyring(profile) => load_keyring_pass(profile)?.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "no password is saved in the keyring",
+                )
+            }),
+        }
+    }
+}
+
+/// Service that passwords are saved under in the OS keyring.
+const KEYRING_SERVICE: &str = "northstar-rcon";
+
+/// Read the password saved in the OS keyring for a profile, or `None` if there isn't one.
+pub fn load_keyring_pass(profile: &str) -> std::io::Result<Option<String>> {
+    match keyring::Entry::new(KEYRING_SERVICE, profile).and_then(|entry| entry.get_password()) {
+        Ok(pass) => Ok(Some(pass)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(std::io::Error::other(err)),
+    }
+}
+
+/// Save the password for a profile in the OS keyring, replacing any that was saved before.
+pub fn save_keyring_pass(profile: &str, pass: &str) -> std::io::Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, profile)
+        .and_then(|entry| entry.set_password(pass))
+        .map_err(std::io::Error::other)
+}
+
+/// Whether a session is connected to its server.
+pub enum ConnectionStatus {
+    Connected,
+
+    /// The connection was lost and is being reconnected in the background.
+    Reconnecting,
+
+    /// The connection was lost or couldn't be made again, with the error that caused it.
+    Disconnected(String),
+}
+
+impl Display for ConnectionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionStatus::Connected => write!(f, "connected"),
+            ConnectionStatus::Reconnecting => write!(f, "reconnecting"),
+            ConnectionStatus::Disconnected(err) => write!(f, "disconnected: {}", err),
+        }
+    }
+}
+
+pub struct Session {
+    pub name: String,
+    pub status: ConnectionStatus,
+    /// Details about the server, shown in the prompt.
+    pub info: ServerInfo,
+    /// When `status` was last run to update `info`.
+    pub polled_at: Option<Instant>,
+    addr: SocketAddr,
+    pass: PassSource,
+    console_logs: bool,
+    write: Option<ClientWrite>,
+    task: JoinHandle<()>,
+}
+
+impl Session {
+    /// The write half of the connection, or `None` if the session is disconnected.
+    pub fn write(&mut self) -> Option<&mut ClientWrite> {
+        self.write.as_mut()
+    }
+
+    /// Remember that console logs were enabled, so they're enabled again after reconnecting.
+    pub fn set_console_logs(&mut self) {
+        self.console_logs = true;
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub enum SessionEvent {
+    Log(String),
+    Closed(northstar_rcon_client::Error),
+    // Boxed since the client halves are much bigger than the other variants
+    Reconnected(Box<(ClientRead, ClientWrite)>),
+    ReconnectFailed(ReconnectError),
+}
+
+/// The sessions that are currently open, one of which is the current session that commands are
+/// sent to.
+pub struct Sessions {
+    sessions: Vec<Session>,
+    current: usize,
+    events: UnboundedSender<(String, SessionEvent)>,
+    /// What sessions are connected and reconnected with.
+    settings: ClientSettings,
+}
+
+impl Sessions {
+    pub fn new(events: UnboundedSender<(String, SessionEvent)>, settings: ClientSettings) -> Self {
+        Sessions {
+            sessions: Vec::new(),
+            current: 0,
+            events,
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> &ClientSettings {
+        &self.settings
+    }
+
+    /// Add a session and make it the current one.
+    ///
+    /// Log lines and errors from the session are sent to the events channel, tagged with the
+    /// session name.
+    pub fn open(
+        &mut self,
+        name: String,
+        addr: SocketAddr,
+        pass: PassSource,
+        read: ClientRead,
+        write: ClientWrite,
+    ) {
+        let task = tokio::spawn(read_loop(name.clone(), read, self.events.clone()));
+
+        self.sessions.push(Session {
+            name,
+            status: ConnectionStatus::Connected,
+            info: ServerInfo::default(),
+            polled_at: None,
+            addr,
+            pass,
+            console_logs: false,
+            write: Some(write),
+            task,
+        });
+        self.current = self.sessions.len() - 1;
+    }
+
+    /// Remove a session. If it was the current session, the most recently opened session becomes
+    /// the current one.
+    pub fn close(&mut self, name: &str) {
+        let index = match self.index_of(name) {
+            Some(index) => index,
+            None => return,
+        };
+
+        self.sessions.remove(index);
+
+        if self.current == index {
+            self.current = self.sessions.len().saturating_sub(1);
+        } else if self.current > index {
+            self.current -= 1;
+        }
+    }
+
+    /// Mark a session as disconnected because of an error, keeping it open so it can be
+    /// reconnected later.
+    pub fn disconnect(&mut self, name: &str, err: &dyn Display) {
+        if let Some(session) = self.get(name) {
+            session.task.abort();
+            session.write = None;
+            session.status = ConnectionStatus::Disconnected(err.to_string());
+        }
+    }
+
+    /// Disconnect a session if it's connected, then reconnect it in the background.
+    ///
+    /// The password is read again from where it originally came from. Once reconnecting finishes
+    /// a [`SessionEvent::Reconnected`] or [`SessionEvent::ReconnectFailed`] event is sent.
+    pub fn reconnect(&mut self, name: &str) -> std::io::Result<()> {
+        let events = self.events.clone();
+        let settings = self.settings.clone();
+        let session = match self.get(name) {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+
+        let client = ReconnectingClient::new(session.addr.to_string(), session.pass.read()?)
+            .enable_console_logs(session.console_logs)
+            .auth_matchers(settings.auth_matchers);
+        let client = match settings.command_filter {
+            Some(filter) => client.command_filter(filter),
+            None => client,
+        };
+        let name = session.name.clone();
+
+        session.task.abort();
+        session.write = None;
+        session.status = ConnectionStatus::Reconnecting;
+        session.task = tokio::spawn(async move {
+            let event = match client.connect().await {
+                Ok(halves) => SessionEvent::Reconnected(Box::new(halves)),
+                Err(err) => SessionEvent::ReconnectFailed(err),
+            };
+            let _ = events.send((name, event));
+        });
+
+        Ok(())
+    }
+
+    /// Use a new connection for a session that was reconnected.
+    pub fn attach(&mut self, name: &str, read: ClientRead, write: ClientWrite) {
+        let events = self.events.clone();
+        if let Some(session) = self.get(name) {
+            session.task.abort();
+            session.task = tokio::spawn(read_loop(session.name.clone(), read, events));
+            session.write = Some(write);
+            session.status = ConnectionStatus::Connected;
+        }
+    }
+
+    /// Make a session the current one. Returns false if no session has the name.
+    pub fn switch(&mut self, name: &str) -> bool {
+        match self.index_of(name) {
+            Some(index) => {
+                self.current = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index_of(name).is_some()
+    }
+
+    pub fn is_connected(&self, name: &str) -> bool {
+        match self.index_of(name) {
+            Some(index) => self.sessions[index].write.is_some(),
+            None => false,
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut Session> {
+        self.sessions.get_mut(self.current)
+    }
+
+    pub fn current_name(&self) -> Option<&str> {
+        Some(&self.sessions.get(self.current)?.name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Session> {
+        self.sessions.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn get(&mut self, name: &str) -> Option<&mut Session> {
+        let index = self.index_of(name)?;
+        self.sessions.get_mut(index)
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.sessions
+            .iter()
+            .position(|session| session.name == name)
+    }
+}
+
+async fn read_loop(
+    name: String,
+    mut read: ClientRead,
+    events: UnboundedSender<(String, SessionEvent)>,
+) {
+    loop {
+        let event = match read.receive_console_log().await {
+            Ok(line) => SessionEvent::Log(line),
+            Err(err) => {
+                let _ = events.send((name, SessionEvent::Closed(err)));
+                return;
+            }
+        };
+
+        if events.send((name.clone(), event)).is_err() {
+            return;
+        }
+    }
+}
@@ -1,11 +1,27 @@
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use crossterm::tty::IsTty;
-use rustyline_async::{Readline, ReadlineError, SharedWriter};
+use rustyline_async::{Readline, ReadlineError, ReadlineEvent, SharedWriter};
 use std::io::{Stderr, Stdout, Write};
 use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 
 pub struct ShellRead {
-    prompt: String,
     inner: ShellReadInner,
+    interrupted: bool,
+    prompt: String,
+    /// Lines ending with a backslash that have been entered so far, which are returned together
+    /// with the next line that doesn't.
+    continued: Vec<String>,
+}
+
+/// The prompt shown while lines are being continued.
+const CONTINUATION_PROMPT: &str = "... ";
+
+pub enum Input {
+    /// A line, or several separated by newlines if they were continued with backslashes.
+    Line(String),
+
+    /// Ctrl+C was pressed, which clears the line. Pressing it again straight away exits.
+    Interrupted,
 }
 
 #[derive(Clone)]
@@ -14,7 +30,7 @@ pub struct ShellWrite {
 }
 
 enum ShellReadInner {
-    Interactive(Readline, SharedWriter),
+    Interactive(Readline),
     Stream(Lines<BufReader<tokio::io::Stdin>>),
 }
 
@@ -28,8 +44,10 @@ pub fn new_shell(prompt: String, disable_interactive: bool) -> (ShellRead, Shell
         let (read_line, writer) = Readline::new(prompt.clone()).unwrap();
         (
             ShellRead {
+                inner: ShellReadInner::Interactive(read_line),
+                interrupted: false,
                 prompt,
-                inner: ShellReadInner::Interactive(read_line, writer.clone()),
+                continued: Vec::new(),
             },
             ShellWrite {
                 inner: ShellWriteInner::Interactive(writer),
@@ -38,8 +56,10 @@ pub fn new_shell(prompt: String, disable_interactive: bool) -> (ShellRead, Shell
     } else {
         (
             ShellRead {
-                prompt,
                 inner: ShellReadInner::Stream(BufReader::new(tokio::io::stdin()).lines()),
+                interrupted: false,
+                prompt,
+                continued: Vec::new(),
             },
             ShellWrite {
                 inner: ShellWriteInner::Stream(std::io::stdout(), std::io::stderr()),
@@ -49,31 +69,73 @@ pub fn new_shell(prompt: String, disable_interactive: bool) -> (ShellRead, Shell
 }
 
 impl ShellRead {
-    pub async fn read_line(&mut self) -> String {
+    /// Read the next line. Returns `None` at the end of non-interactive input.
+    ///
+    /// Lines ending with a backslash are continued onto the next line, and returned together
+    /// once a line that doesn't end with one is entered. This is safe to cancel, since the lines
+    /// entered so far are kept for the next call.
+    pub async fn read_line(&mut self) -> Option<Input> {
+        loop {
+            let line = match self.read_single_line().await {
+                Some(Input::Line(line)) => line,
+                Some(Input::Interrupted) => {
+                    // Ctrl+C throws away the lines entered so far
+                    if !self.continued.is_empty() {
+                        self.continued.clear();
+                        self.show_prompt(&self.prompt.clone());
+                    }
+                    return Some(Input::Interrupted);
+                }
+                None if self.continued.is_empty() => return None,
+                None => String::new(),
+            };
+
+            if let Some(line) = line.trim_end().strip_suffix('\\') {
+                if self.continued.is_empty() {
+                    self.show_prompt(CONTINUATION_PROMPT);
+                }
+                self.continued.push(line.to_string());
+                continue;
+            }
+
+            if self.continued.is_empty() {
+                return Some(Input::Line(line));
+            }
+
+            self.continued.push(line);
+            self.show_prompt(&self.prompt.clone());
+            return Some(Input::Line(std::mem::take(&mut self.continued).join("\n")));
+        }
+    }
+
+    async fn read_single_line(&mut self) -> Option<Input> {
         match &mut self.inner {
-            ShellReadInner::Interactive(read, writer) => {
+            ShellReadInner::Interactive(read) => {
                 let line = match read.readline().await {
-                    Ok(line) => line,
+                    Ok(ReadlineEvent::Line(line)) => line,
+                    Ok(ReadlineEvent::Eof) | Err(ReadlineError::Closed) => {
+                        proc_exit::Code::SIGHUP.process_exit()
+                    }
+                    Ok(ReadlineEvent::Interrupted) if self.interrupted => {
+                        proc_exit::Code::SIGINT.process_exit()
+                    }
+                    Ok(ReadlineEvent::Interrupted) => {
+                        self.interrupted = true;
+                        return Some(Input::Interrupted);
+                    }
                     Err(ReadlineError::IO(err)) => {
                         eprintln!("An error occurred: {}", err);
                         proc_exit::Code::UNKNOWN.process_exit();
                     }
-                    Err(ReadlineError::Eof | ReadlineError::Closed) => {
-                        proc_exit::Code::SIGHUP.process_exit()
-                    }
-                    Err(ReadlineError::Interrupted) => proc_exit::Code::SIGINT.process_exit(),
                 };
 
+                self.interrupted = false;
                 read.add_history_entry(line.clone());
 
-                // echo back the line
-                writeln!(writer, "{}{}", self.prompt, line).unwrap();
-
-                line
+                Some(Input::Line(line))
             }
             ShellReadInner::Stream(stream) => match stream.next_line().await {
-                Ok(Some(line)) => line,
-                Ok(None) => proc_exit::Code::UNKNOWN.process_exit(),
+                Ok(line) => line.map(Input::Line),
                 Err(err) => {
                     eprintln!("An error occurred: {}", err);
                     proc_exit::Code::UNKNOWN.process_exit();
@@ -81,6 +143,47 @@ impl ShellRead {
             },
         }
     }
+
+    /// Forget that Ctrl+C was pressed, so the next press doesn't exit.
+    pub fn clear_interrupt(&mut self) {
+        self.interrupted = false;
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        matches!(self.inner, ShellReadInner::Interactive(_))
+    }
+
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = prompt.to_string();
+        if self.continued.is_empty() {
+            self.show_prompt(prompt);
+        }
+    }
+
+    fn show_prompt(&mut self, prompt: &str) {
+        if let ShellReadInner::Interactive(read) = &mut self.inner {
+            read.update_prompt(prompt).unwrap();
+        }
+    }
+
+    pub fn prompt_password(&mut self, prompt: &str) -> std::io::Result<String> {
+        self.suspend(|| rpassword::prompt_password(prompt))
+    }
+
+    /// Let something else, like a password prompt or a pager, have the terminal while `f` runs.
+    pub fn suspend<T>(&mut self, f: impl FnOnce() -> std::io::Result<T>) -> std::io::Result<T> {
+        match &mut self.inner {
+            ShellReadInner::Interactive(read) => {
+                // Show any pending output first, so it isn't drawn over the other program
+                read.flush().map_err(readline_to_io_error)?;
+                disable_raw_mode()?;
+                let result = f();
+                enable_raw_mode()?;
+                result
+            }
+            ShellReadInner::Stream(_) => f(),
+        }
+    }
 }
 
 impl ShellWrite {
@@ -109,3 +212,10 @@ impl Clone for ShellWriteInner {
         }
     }
 }
+
+fn readline_to_io_error(err: ReadlineError) -> std::io::Error {
+    match err {
+        ReadlineError::IO(err) => err,
+        ReadlineError::Closed => std::io::ErrorKind::BrokenPipe.into(),
+    }
+}
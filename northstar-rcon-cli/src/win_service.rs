@@ -0,0 +1,69 @@
+use crate::Args;
+use clap::Parser;
+use std::ffi::OsString;
+use std::sync::OnceLock;
+use std::time::Duration;
+use windows_service::service::{
+    ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{
+    self, ServiceControlHandlerResult, ServiceStatusHandle,
+};
+use windows_service::{define_windows_service, service_dispatcher};
+
+/// Name the service is registered with. Services running in their own process can be installed
+/// under any name, so this is only used when talking to the service control manager.
+const SERVICE_NAME: &str = "nsrcon";
+
+static STATUS_HANDLE: OnceLock<ServiceStatusHandle> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hand this thread to the service control manager, which runs the client on another thread
+/// until the service is stopped.
+pub fn start() {
+    if let Err(err) = service_dispatcher::start(SERVICE_NAME, ffi_service_main) {
+        eprintln!("Can't start the Windows service: {}", err);
+        proc_exit::Code::OS_ERR.process_exit();
+    }
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    let status_handle = match service_control_handler::register(SERVICE_NAME, handle_control) {
+        Ok(status_handle) => status_handle,
+        Err(_) => proc_exit::Code::OS_ERR.process_exit(),
+    };
+    let _ = STATUS_HANDLE.set(status_handle);
+    set_state(ServiceState::Running);
+
+    // The service is configured with the same arguments as the client was started with
+    crate::run(Args::parse());
+}
+
+fn handle_control(control: ServiceControl) -> ServiceControlHandlerResult {
+    match control {
+        ServiceControl::Stop => {
+            set_state(ServiceState::Stopped);
+            proc_exit::Code::SUCCESS.process_exit();
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    }
+}
+
+fn set_state(state: ServiceState) {
+    if let Some(status_handle) = STATUS_HANDLE.get() {
+        let _ = status_handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: match state {
+                ServiceState::Running => ServiceControlAccept::STOP,
+                _ => ServiceControlAccept::empty(),
+            },
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        });
+    }
+}
@@ -0,0 +1,122 @@
+//! Benchmarks for encoding requests and decoding responses.
+//!
+//! Responses are decoded from a capture of console logs like the ones a busy Northstar server
+//! sends, without a socket, so only the protocol code is measured. Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use northstar_rcon_types::raw::{Response, Response_t};
+use northstar_rcon_types::{
+    console_log_range, decode_response, encode_request, raw, split_frame, Request,
+};
+use protobuf::Message;
+
+// A mix of engine lines, script prints and `status` output, as they arrive from a server
+const CAPTURE: &[&str] = &[
+    "[12:34:56] [NORTHSTAR] [info] Loading playlist overrides\n",
+    "[12:34:56] [SCRIPT SV] [info] Player Pilot One connected\n",
+    "[12:34:57] [SCRIPT SV] [info] CodeCallback_OnClientConnected: Pilot One (1000123)\n",
+    "[12:34:57] [NORTHSTAR] [warning] Mod Northstar.Custom has no mod.json version field\n",
+    "[12:34:58] [SCRIPT SV] [error] SCRIPT ERROR: [SERVER] index 'GetTitanSoul' does not exist\n",
+    "[12:34:58] [ENGINE SV] [info] Spawned 12 npcs in 0.4ms\n",
+    "hostname: Northstar Server\n",
+    "version : 2.0.0.0/1.14.0 1.14.0 secure\n",
+    "udp/ip  : 10.0.0.1:37015\n",
+    "os      : Windows\n",
+    "type    : community dedicated\n",
+    "map     : mp_glitch at: 0 x, 0 y, 0 z\n",
+    "players : 3 humans, 0 bots (16 max)\n",
+    "# userid name uniqueid connected ping loss state rate adr\n",
+    "#  2 \"Pilot One\" 1000123 05:12 45 0 active 786432 10.0.0.20:37005\n",
+    "#  3 \"xX_Sniper\" 1000456 01:02:03 120 0 active 786432 10.0.0.21:37005\n",
+    "#  4 \"Pilot\" 1000789 00:10 30 0 active 786432 10.0.0.22:37005\n",
+    "#end\n",
+];
+
+// Encode a response, including its length, like the server sends it
+fn frame(response_type: Response_t, buf: &str) -> Vec<u8> {
+    let mut response = Response::new();
+    response.responseID = Some(-1);
+    response.responseType = Some(response_type.into());
+    response.responseBuf = Some(buf.to_string());
+
+    let body = response.write_to_bytes().unwrap();
+    let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(&body);
+    frame
+}
+
+// The capture as one stream of framed console log responses
+fn capture_stream() -> Vec<u8> {
+    CAPTURE
+        .iter()
+        .flat_map(|line| frame(Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG, line))
+        .collect()
+}
+
+// The capture's frames, without their lengths
+fn capture_frames(stream: &[u8]) -> Vec<&[u8]> {
+    let mut frames = Vec::new();
+    let mut rest = stream;
+    while let Some((frame, after)) = split_frame(rest) {
+        frames.push(frame);
+        rest = after;
+    }
+    frames
+}
+
+fn encode(c: &mut Criterion) {
+    let exec_command = raw::Request::from(Request::ExecCommand {
+        cmd: "kick \"Pilot One\"",
+    });
+    let set_value = raw::Request::from(Request::SetValue {
+        var: "sv_cheats",
+        val: "1",
+    });
+    let mut buf = Vec::new();
+
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("exec_command", |b| {
+        b.iter(|| {
+            buf.clear();
+            encode_request(black_box(&exec_command), &mut buf).unwrap()
+        })
+    });
+    group.bench_function("set_value", |b| {
+        b.iter(|| {
+            buf.clear();
+            encode_request(black_box(&set_value), &mut buf).unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let stream = capture_stream();
+    let frames = capture_frames(&stream);
+    assert_eq!(frames.len(), CAPTURE.len());
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(CAPTURE.len() as u64));
+    group.bench_function("split_frame", |b| {
+        b.iter(|| capture_frames(black_box(&stream)))
+    });
+    group.bench_function("decode_response", |b| {
+        b.iter(|| {
+            for frame in &frames {
+                decode_response(black_box(frame)).unwrap();
+            }
+        })
+    });
+    group.bench_function("console_log_range", |b| {
+        b.iter(|| {
+            for frame in &frames {
+                console_log_range(black_box(frame)).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+use crate::{ClientWrite, Request};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A request sent through an [`AuditLog`], recorded by an [`AuditSink`].
+#[derive(Debug)]
+pub struct AuditEntry<'a> {
+    /// When the request started being sent.
+    pub time: SystemTime,
+
+    /// Who or what sent the request, like the name of a panel's user.
+    pub origin: &'a str,
+    pub request: Request<'a>,
+
+    /// Whether the request was sent. The server doesn't say whether a command succeeded.
+    pub result: Result<(), &'a crate::Error>,
+
+    /// How long sending the request took.
+    pub latency: Duration,
+}
+
+/// Somewhere an [`AuditLog`] records requests, such as a file or a database.
+///
+/// Implemented for closures taking an [`AuditEntry`].
+pub trait AuditSink {
+    fn record(&mut self, entry: &AuditEntry<'_>);
+}
+
+impl<F: FnMut(&AuditEntry<'_>)> AuditSink for F {
+    fn record(&mut self, entry: &AuditEntry<'_>) {
+        self(entry)
+    }
+}
+
+/// A writer that records every request it sends, and who it was sent for, in an [`AuditSink`].
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::{connect, AuditEntry, AuditLog};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (_, write) = client.authenticate("password123").await.unwrap();
+///
+///     let mut write = AuditLog::new(write, |entry: &AuditEntry| {
+///         println!(
+///             "{:?} {} sent {:?} in {:?}: {:?}",
+///             entry.time, entry.origin, entry.request, entry.latency, entry.result
+///         );
+///     });
+///
+///     write.exec_command("alice", "quit").await.unwrap();
+/// }
+/// ```
+pub struct AuditLog<S> {
+    write: ClientWrite,
+    sink: S,
+}
+
+impl<S: AuditSink> AuditLog<S> {
+    /// Wrap a writer, recording its requests in `sink`.
+    pub fn new(write: ClientWrite, sink: S) -> Self {
+        AuditLog { write, sink }
+    }
+
+    /// Execute a command remotely for `origin`.
+    ///
+    /// See [`ClientWrite::exec_command`].
+    pub async fn exec_command(&mut self, origin: &str, cmd: &str) -> crate::Result<()> {
+        let time = SystemTime::now();
+        let start = Instant::now();
+        let result = self.write.exec_command(cmd).await;
+        self.record(origin, Request::ExecCommand { cmd }, time, start, &result);
+        result
+    }
+
+    /// Set the value of a ConVar for `origin`.
+    ///
+    /// See [`ClientWrite::set_value`].
+    pub async fn set_value(&mut self, origin: &str, var: &str, val: &str) -> crate::Result<()> {
+        let time = SystemTime::now();
+        let start = Instant::now();
+        let result = self.write.set_value(var, val).await;
+        self.record(origin, Request::SetValue { var, val }, time, start, &result);
+        result
+    }
+
+    /// Enable console logs being sent to RCON clients, for `origin`.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub async fn enable_console_logs(&mut self, origin: &str) -> crate::Result<()> {
+        let time = SystemTime::now();
+        let start = Instant::now();
+        let result = self.write.enable_console_logs().await;
+        self.record(origin, Request::EnableConsoleLogs, time, start, &result);
+        result
+    }
+
+    /// Get the writer and the sink back.
+    pub fn into_inner(self) -> (ClientWrite, S) {
+        (self.write, self.sink)
+    }
+
+    fn record(
+        &mut self,
+        origin: &str,
+        request: Request<'_>,
+        time: SystemTime,
+        start: Instant,
+        result: &crate::Result<()>,
+    ) {
+        self.sink.record(&AuditEntry {
+            time,
+            origin,
+            request,
+            result: result.as_ref().copied(),
+            latency: start.elapsed(),
+        });
+    }
+}
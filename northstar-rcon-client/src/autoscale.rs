@@ -0,0 +1,226 @@
+use crate::{PopulationMonitor, ServerEvent, ServerSubscriber, SubscriberEvent};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Callbacks an [`Autoscaler`] makes when servers have been empty or full for a while, such as
+/// to stop or start instances with a hosting provider.
+///
+/// Each callback is made once each time a server becomes empty or full and stays that way. They
+/// run on the autoscaler's task, so anything slow should be spawned onto another task.
+pub trait AutoscaleHook<L> {
+    /// A server has had no players for `idle`.
+    fn server_idle_for(&mut self, label: &L, idle: Duration);
+
+    /// A server has had at least the autoscaler's full player count for `full`.
+    fn server_full_for(&mut self, label: &L, full: Duration) {
+        let _ = (label, full);
+    }
+}
+
+/// Watches player counts on the servers of a [`ServerManager`] and calls an [`AutoscaleHook`]
+/// when they've been empty or full for long enough.
+///
+/// Player counts are read from `status` output in the console logs. The manager has to be
+/// running `status`, which can be set up with [`ServerManager::population_monitor`], and console
+/// logs have to be enabled. A server's count is forgotten when it disconnects.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::{
+///     AutoscaleHook, Autoscaler, PopulationMonitor, ReconnectingClient, ServerManager,
+/// };
+/// use std::time::Duration;
+///
+/// struct StopWhenIdle;
+///
+/// impl AutoscaleHook<&'static str> for StopWhenIdle {
+///     fn server_idle_for(&mut self, label: &&'static str, idle: Duration) {
+///         println!("{} has been empty for {:?}, stopping it", label, idle);
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let autoscaler = Autoscaler::new(StopWhenIdle).idle_after(Duration::from_secs(15 * 60));
+///     let mut manager = ServerManager::new()
+///         .population_monitor(PopulationMonitor::new(Duration::from_secs(60)));
+///     let subscriber = manager.subscribe();
+///
+///     let client = ReconnectingClient::new("10.0.0.1:37015", "password123")
+///         .enable_console_logs(true);
+///     manager.add("eu-1", client);
+///
+///     tokio::spawn(autoscaler.run(subscriber));
+///     loop {
+///         manager.next_event().await;
+///     }
+/// }
+/// ```
+///
+/// [`ServerManager`]: crate::ServerManager
+/// [`ServerManager::population_monitor`]: crate::ServerManager::population_monitor
+#[derive(Debug)]
+pub struct Autoscaler<L, H> {
+    hook: H,
+    idle_after: Duration,
+    full: Option<(u32, Duration)>,
+    servers: HashMap<L, Population>,
+}
+
+// What's known about one server's player count
+#[derive(Debug)]
+struct Population {
+    monitor: PopulationMonitor,
+    idle: Option<Episode>,
+    full: Option<Episode>,
+}
+
+// A time a server has been idle or full since, and whether the hook has been called for it
+#[derive(Debug, Clone, Copy)]
+struct Episode {
+    since: Instant,
+    reported: bool,
+}
+
+impl<L: Clone + Eq + Hash, H: AutoscaleHook<L>> Autoscaler<L, H> {
+    /// Create an autoscaler that calls `hook` once a server has been empty for 10 minutes, and
+    /// never reports full servers.
+    pub fn new(hook: H) -> Self {
+        Autoscaler {
+            hook,
+            idle_after: Duration::from_secs(10 * 60),
+            full: None,
+            servers: HashMap::new(),
+        }
+    }
+
+    /// Set how long a server has to be empty before [`AutoscaleHook::server_idle_for`] is
+    /// called.
+    pub fn idle_after(mut self, idle: Duration) -> Self {
+        self.idle_after = idle;
+        self
+    }
+
+    /// Call [`AutoscaleHook::server_full_for`] once a server has had at least `players` players
+    /// for `full`.
+    pub fn full_after(mut self, players: u32, full: Duration) -> Self {
+        self.full = Some((players, full));
+        self
+    }
+
+    /// Watch the events from a manager's subscriber, calling the hook as servers stay empty or
+    /// full. Returns the hook once the manager has been dropped.
+    pub async fn run(mut self, mut events: ServerSubscriber<L>) -> H {
+        loop {
+            let next_deadline = self.next_deadline();
+            let event = tokio::select! {
+                event = events.recv() => match event {
+                    Some(event) => Some(event),
+                    None => return self.hook,
+                },
+                _ = sleep_until(next_deadline) => None,
+            };
+
+            if let Some(SubscriberEvent::Event(label, event)) = event {
+                self.handle_event(label, event);
+            }
+            self.report_due();
+        }
+    }
+
+    fn handle_event(&mut self, label: L, event: ServerEvent) {
+        let line = match event {
+            ServerEvent::ConsoleLog(line) => line,
+            ServerEvent::Disconnected(_) | ServerEvent::Failed(_) => {
+                self.servers.remove(&label);
+                return;
+            }
+            _ => return,
+        };
+
+        let population = self.servers.entry(label).or_insert_with(|| Population {
+            monitor: PopulationMonitor::new(Duration::ZERO),
+            idle: None,
+            full: None,
+        });
+        population.monitor.observe(&line);
+        let count = match population.monitor.last_count() {
+            Some(count) => count,
+            None => return,
+        };
+
+        let now = Instant::now();
+        update_episode(&mut population.idle, count == 0, now);
+        let is_full = self.full.is_some_and(|(players, _)| count >= players);
+        update_episode(&mut population.full, is_full, now);
+    }
+
+    // Call the hook for every server that's been empty or full for long enough
+    fn report_due(&mut self) {
+        let now = Instant::now();
+        for (label, population) in &mut self.servers {
+            if let Some(idle) = due(&mut population.idle, self.idle_after, now) {
+                self.hook.server_idle_for(label, idle);
+            }
+            if let Some((_, full_after)) = self.full {
+                if let Some(full) = due(&mut population.full, full_after, now) {
+                    self.hook.server_full_for(label, full);
+                }
+            }
+        }
+    }
+
+    // When the next server will have been empty or full for long enough
+    fn next_deadline(&self) -> Option<Instant> {
+        let full_after = self.full.map(|(_, full_after)| full_after);
+        self.servers
+            .values()
+            .flat_map(|population| {
+                let idle = pending(population.idle).map(|since| since + self.idle_after);
+                let full = pending(population.full).zip(full_after);
+                [idle, full.map(|(since, full_after)| since + full_after)]
+            })
+            .flatten()
+            .min()
+    }
+}
+
+fn update_episode(episode: &mut Option<Episode>, active: bool, now: Instant) {
+    match (active, episode.is_some()) {
+        (true, false) => {
+            *episode = Some(Episode {
+                since: now,
+                reported: false,
+            })
+        }
+        (false, true) => *episode = None,
+        _ => {}
+    }
+}
+
+fn pending(episode: Option<Episode>) -> Option<Instant> {
+    episode
+        .filter(|episode| !episode.reported)
+        .map(|episode| episode.since)
+}
+
+// If an episode has lasted long enough and hasn't been reported yet, mark it as reported and
+// return how long it's lasted
+fn due(episode: &mut Option<Episode>, after: Duration, now: Instant) -> Option<Duration> {
+    let episode = episode.as_mut().filter(|episode| !episode.reported)?;
+    let lasted = now.duration_since(episode.since);
+    if lasted < after {
+        return None;
+    }
+    episode.reported = true;
+    Some(lasted)
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,284 @@
+use crate::ClientRead;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// What a [`BufferedRead`] does with a new console log line when its buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Stop reading from the server until there's space, so the server waits instead.
+    Block,
+
+    /// Drop the oldest line in the buffer to make space.
+    DropOldest,
+
+    /// Drop the new line.
+    DropNewest,
+
+    /// Stop reading, and return [`Error::Overflow`](crate::Error::Overflow) once the lines in the
+    /// buffer have been received.
+    Error,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    lines: VecDeque<String>,
+    error: Option<crate::Error>,
+    closed: bool,
+    dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    line_added: Notify,
+    line_removed: Notify,
+}
+
+/// The read end of a client that reads console logs into a bounded buffer in the background.
+///
+/// Created with [`ClientRead::into_buffered`].
+#[derive(Debug)]
+pub struct BufferedRead {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl ClientRead {
+    /// Start reading console log lines into a buffer on a background task, keeping up to
+    /// `capacity` lines until they're received.
+    ///
+    /// By default lines are only read from the server when [`receive_console_log`] is called,
+    /// so a slow reader makes the server wait. `policy` chooses what happens instead when the
+    /// buffer is full.
+    ///
+    /// Settings like [`set_duplicate_window`] still apply to lines read into the buffer, but the
+    /// history can no longer be read, so set them before calling this.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is `0`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, OverflowPolicy};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.enable_console_logs().await.unwrap();
+    ///     let mut read = read.into_buffered(10_000, OverflowPolicy::DropOldest);
+    ///
+    ///     loop {
+    ///         let line = read.receive_console_log().await.unwrap();
+    ///         println!("> {}", line);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    /// [`set_duplicate_window`]: ClientRead::set_duplicate_window
+    pub fn into_buffered(self, capacity: usize, policy: OverflowPolicy) -> BufferedRead {
+        assert!(capacity > 0, "buffer capacity must be more than 0");
+
+        let shared = Arc::new(Shared::default());
+        let task = tokio::spawn(fill_buffer(self, capacity, policy, shared.clone()));
+        BufferedRead { shared, task }
+    }
+}
+
+impl BufferedRead {
+    /// Receive the next console log line from the buffer, waiting for one if it's empty.
+    ///
+    /// Once the buffer is empty, an error that stopped lines being read is returned, and after
+    /// that [`std::io::ErrorKind::NotConnected`].
+    pub async fn receive_console_log(&mut self) -> crate::Result<String> {
+        loop {
+            let line_added = self.shared.line_added.notified();
+
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                if let Some(line) = queue.lines.pop_front() {
+                    self.shared.line_removed.notify_one();
+                    return Ok(line);
+                }
+                if let Some(err) = queue.error.take() {
+                    return Err(err);
+                }
+                if queue.closed {
+                    return Err(std::io::Error::from(std::io::ErrorKind::NotConnected).into());
+                }
+            }
+
+            line_added.await;
+        }
+    }
+
+    /// How many lines have been dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.queue.lock().unwrap().dropped
+    }
+}
+
+impl Drop for BufferedRead {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn fill_buffer(
+    mut read: ClientRead,
+    capacity: usize,
+    policy: OverflowPolicy,
+    shared: Arc<Shared>,
+) {
+    loop {
+        let line = match read.receive_console_log().await {
+            Ok(line) => line,
+            Err(err) => {
+                stop(&shared, err);
+                return;
+            }
+        };
+
+        loop {
+            let line_removed = shared.line_removed.notified();
+
+            {
+                let mut queue = shared.queue.lock().unwrap();
+                if queue.lines.len() < capacity {
+                    queue.lines.push_back(line);
+                    break;
+                }
+
+                match policy {
+                    OverflowPolicy::Block => {}
+                    OverflowPolicy::DropOldest => {
+                        queue.lines.pop_front();
+                        queue.lines.push_back(line);
+                        queue.dropped += 1;
+                        break;
+                    }
+                    OverflowPolicy::DropNewest => {
+                        queue.dropped += 1;
+                        break;
+                    }
+                    OverflowPolicy::Error => {
+                        drop(queue);
+                        stop(&shared, crate::Error::Overflow);
+                        return;
+                    }
+                }
+            }
+
+            line_removed.await;
+        }
+
+        shared.line_added.notify_one();
+    }
+}
+
+fn stop(shared: &Shared, err: crate::Error) {
+    let mut queue = shared.queue.lock().unwrap();
+    queue.error = Some(err);
+    queue.closed = true;
+    drop(queue);
+    shared.line_added.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestConnection, TestServer};
+    use std::time::Duration;
+
+    // Connect to a server that sends `lines`, and buffer them
+    async fn buffered(
+        server: &TestServer,
+        capacity: usize,
+        policy: OverflowPolicy,
+        lines: &[&str],
+    ) -> (BufferedRead, TestConnection) {
+        let (read, _, mut connection) = server.connect().await;
+        let read = read.into_buffered(capacity, policy);
+        for line in lines {
+            connection.send_log(line).await;
+        }
+        (read, connection)
+    }
+
+    // Wait for the background task to get the buffer into some state
+    async fn wait_for(read: &BufferedRead, done: impl Fn(&Queue) -> bool) {
+        while !done(&read.shared.queue.lock().unwrap()) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    async fn receive(read: &mut BufferedRead, count: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let line = read.receive_console_log().await.unwrap();
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn drop_oldest() {
+        let server = TestServer::bind().await;
+        let (mut read, _connection) =
+            buffered(&server, 2, OverflowPolicy::DropOldest, &["a", "b", "c"]).await;
+
+        wait_for(&read, |queue| queue.dropped == 1).await;
+        assert_eq!(read.dropped(), 1);
+        assert_eq!(receive(&mut read, 2).await, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn drop_newest() {
+        let server = TestServer::bind().await;
+        let (mut read, _connection) = buffered(
+            &server,
+            2,
+            OverflowPolicy::DropNewest,
+            &["a", "b", "c", "d"],
+        )
+        .await;
+
+        wait_for(&read, |queue| queue.dropped == 2).await;
+        assert_eq!(read.dropped(), 2);
+        assert_eq!(receive(&mut read, 2).await, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn error_after_buffered_lines() {
+        let server = TestServer::bind().await;
+        let (mut read, _connection) =
+            buffered(&server, 2, OverflowPolicy::Error, &["a", "b", "c"]).await;
+
+        wait_for(&read, |queue| queue.closed).await;
+        assert_eq!(receive(&mut read, 2).await, ["a", "b"]);
+        assert!(matches!(
+            read.receive_console_log().await,
+            Err(crate::Error::Overflow)
+        ));
+        assert_eq!(read.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn block() {
+        let server = TestServer::bind().await;
+        let (mut read, _connection) =
+            buffered(&server, 1, OverflowPolicy::Block, &["a", "b", "c"]).await;
+
+        // The buffer stays full while the rest wait to be read from the server
+        wait_for(&read, |queue| queue.lines.len() == 1).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(read.shared.queue.lock().unwrap().lines.len(), 1);
+
+        assert_eq!(receive(&mut read, 3).await, ["a", "b", "c"]);
+        assert_eq!(read.dropped(), 0);
+    }
+}
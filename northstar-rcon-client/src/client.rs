@@ -1,5 +1,12 @@
+use crate::command::quoted_command;
+use crate::dedupe::Dedupe;
+use crate::history::{History, HistoryEntry};
 use crate::inner_client;
-use crate::inner_client::{InnerClientRead, InnerClientWrite, Request, Response};
+use crate::inner_client::{AuthMatchers, InnerClientRead, InnerClientWrite, Request, Response};
+use crate::CommandFilter;
+use bytes::Bytes;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
 use tokio::net::{TcpStream, ToSocketAddrs};
 
 /// A connected but not yet authenticated RCON client.
@@ -31,6 +38,7 @@ use tokio::net::{TcpStream, ToSocketAddrs};
 pub struct NotAuthenticatedClient {
     read: InnerClientRead,
     write: InnerClientWrite,
+    auth_matchers: AuthMatchers,
 }
 
 /// An error describing why an authentication request failed.
@@ -38,11 +46,17 @@ pub struct NotAuthenticatedClient {
 pub enum AuthError {
     /// The request failed because an invalid password was used.
     #[error("invalid password")]
-    InvalidPassword,
+    InvalidPassword {
+        /// The message the server sent with its response, such as `Admin password incorrect.`
+        message: String,
+    },
 
     /// The request failed because this user or IP address is banned.
     #[error("banned")]
-    Banned,
+    Banned {
+        /// The message the server sent with its response, such as `Go away.`
+        message: String,
+    },
 
     /// The request failed due to a socket or protocol error.
     #[error(transparent)]
@@ -74,6 +88,29 @@ pub enum AuthError {
 /// ```
 pub struct ClientRead {
     read: InnerClientRead,
+    history: History,
+    dedupe: Dedupe,
+    // Whether responses that aren't understood are returned by `receive_event`
+    unknown_responses: bool,
+    // Whether unexpected responses are errors instead of being skipped
+    strict: bool,
+}
+
+/// Something received by [`ClientRead::receive_event`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A console log line, like from [`ClientRead::receive_console_log`].
+    ConsoleLog(String),
+
+    /// A response this crate doesn't understand. Only received after
+    /// [`ClientRead::set_unknown_responses`] enables it.
+    Unknown {
+        /// The response type number, if the server sent one.
+        response_type: Option<i32>,
+
+        /// The text the server sent with the response, if any.
+        payload: Option<String>,
+    },
 }
 
 /// The write end of a connected and authenticated RCON client.
@@ -81,6 +118,11 @@ pub struct ClientRead {
 /// Commands can be sent by the writer while log messages are received from the reader, and
 /// vice-versa. The underlying connection will close when both the reader and writer are closed.
 ///
+/// Sending is cancel safe. If a send is cancelled, for example by a timeout, before any of the
+/// request was written it's dropped, and otherwise the rest of it is sent before the next
+/// request. If writing fails part way through a request, every later send returns
+/// [`Error::CorruptedStream`](crate::Error::CorruptedStream).
+///
 /// # Example
 /// ```rust,no_run
 /// use northstar_rcon_client::connect;
@@ -102,13 +144,85 @@ impl NotAuthenticatedClient {
     pub(crate) async fn new<A: ToSocketAddrs>(addr: A) -> crate::Result<Self> {
         let stream = TcpStream::connect(addr).await?;
 
+        #[cfg(feature = "tracing")]
+        tracing::info!(addr = ?stream.peer_addr().ok(), "connected");
+
         let (read, write) = stream.into_split();
         Ok(NotAuthenticatedClient {
             read: InnerClientRead::new(read),
             write: InnerClientWrite::new(write),
+            auth_matchers: AuthMatchers::default(),
         })
     }
 
+    /// Set the text used to tell why authentication failed, for servers that word their
+    /// responses differently.
+    ///
+    /// See [`AuthOutcome::parse`](crate::AuthOutcome::parse) for how responses are read.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, AuthMatchers};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut matchers = AuthMatchers::default();
+    ///     matchers.invalid_password.push("Wrong password".to_string());
+    ///
+    ///     let client = connect("localhost:37015").await.unwrap().auth_matchers(matchers);
+    ///     let (_read, _write) = client.authenticate("password123").await.unwrap();
+    /// }
+    /// ```
+    pub fn auth_matchers(mut self, matchers: AuthMatchers) -> Self {
+        self.auth_matchers = matchers;
+        self
+    }
+
+    /// Pass a record of every frame sent and received on this connection to `callback`,
+    /// including while authenticating.
+    ///
+    /// The callback runs on whichever task is sending or receiving, so it should be quick.
+    ///
+    /// This requires the `protocol-trace` feature.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015")
+    ///         .await
+    ///         .unwrap()
+    ///         .protocol_trace(|record| eprintln!("{}", record));
+    ///
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.exec_command("status").await.unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "protocol-trace")]
+    pub fn protocol_trace(
+        self,
+        callback: impl Fn(&crate::protocol_trace::FrameRecord) + Send + Sync + 'static,
+    ) -> Self {
+        self.tracer(Some(crate::protocol_trace::Tracer::new(callback)))
+    }
+
+    #[cfg(feature = "protocol-trace")]
+    pub(crate) fn tracer(mut self, tracer: Option<crate::protocol_trace::Tracer>) -> Self {
+        self.read.set_tracer(tracer.clone());
+        self.write.set_tracer(tracer);
+        self
+    }
+
+    /// Check every request against a filter once authenticated.
+    ///
+    /// See [`ClientWrite::set_command_filter`].
+    pub fn command_filter(mut self, filter: CommandFilter) -> Self {
+        self.write.set_filter(Some(filter));
+        self
+    }
+
     /// Attempt to authenticate with the RCON server.
     ///
     /// If the authentication attempt is successful this client will become a
@@ -156,28 +270,95 @@ impl NotAuthenticatedClient {
         }
 
         // Wait until a successful authentication response is received
-        loop {
-            match self.read.receive().await {
-                Ok(Response::Auth { res: Ok(()) }) => break,
+        let err = loop {
+            match self.read.receive_with(&self.auth_matchers).await {
+                Ok(Response::Auth { res: Ok(()), .. }) => break None,
                 Ok(Response::Auth {
                     res: Err(inner_client::AuthError::InvalidPassword),
-                }) => return Err((self, AuthError::InvalidPassword)),
+                    msg,
+                }) => break Some(AuthError::InvalidPassword { message: msg }),
                 Ok(Response::Auth {
                     res: Err(inner_client::AuthError::Banned),
-                }) => return Err((self, AuthError::Banned)),
+                    msg,
+                }) => break Some(AuthError::Banned { message: msg }),
                 Ok(_) => {
-                    // todo: log message indicating something was skipped?
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("skipped a response while authenticating");
                     continue;
                 }
-                Err(err) => return Err((self, AuthError::Fatal(err))),
+                Err(err) => break Some(AuthError::Fatal(err)),
             }
+        };
+
+        #[cfg(feature = "tracing")]
+        match &err {
+            None => tracing::info!("authenticated"),
+            Some(err) => tracing::info!(%err, "authentication failed"),
+        }
+
+        if let Some(err) = err {
+            return Err((self, err));
         }
 
         Ok((
-            ClientRead { read: self.read },
+            ClientRead {
+                read: self.read,
+                history: History::default(),
+                dedupe: Dedupe::default(),
+                unknown_responses: false,
+                strict: false,
+            },
             ClientWrite { write: self.write },
         ))
     }
+
+    /// Attempt to authenticate with each password in a list, in order.
+    ///
+    /// Stops at the first password that succeeds, returning its index in the list alongside the
+    /// [`ClientRead`]/[`ClientWrite`] pair. Invalid passwords move on to the next one, but any
+    /// other error stops immediately and is returned with the client, like with
+    /// [`authenticate`]. If every password is invalid, the last [`AuthError::InvalidPassword`] is
+    /// returned, or one with an empty message if the list is empty.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///
+    ///     let passwords = ["new-password", "old-password"];
+    ///     match client.authenticate_any(passwords).await {
+    ///         Ok((index, _, _)) => println!("Authenticated with password {}", index),
+    ///         Err((_, err)) => println!("Authentication failed: {}", err),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`authenticate`]: NotAuthenticatedClient::authenticate
+    pub async fn authenticate_any<I>(
+        mut self,
+        passwords: I,
+    ) -> Result<(usize, ClientRead, ClientWrite), (NotAuthenticatedClient, AuthError)>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut message = String::new();
+        for (index, pass) in passwords.into_iter().enumerate() {
+            match self.authenticate(pass.as_ref()).await {
+                Ok((read, write)) => return Ok((index, read, write)),
+                Err((client, AuthError::InvalidPassword { message: msg })) => {
+                    self = client;
+                    message = msg;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err((self, AuthError::InvalidPassword { message }))
+    }
 }
 
 impl ClientWrite {
@@ -217,6 +398,161 @@ impl ClientWrite {
         self.write.send(Request::ExecCommand { cmd }).await
     }
 
+    /// Send a chat message to every player, from the server.
+    ///
+    /// The message is [quoted](crate::quote), so it's sent as written as long as it has no double
+    /// quotes or line breaks.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.say("Restarting in 5 minutes; finish your match!").await.unwrap();
+    /// }
+    /// ```
+    pub async fn say(&mut self, message: &str) -> crate::Result<()> {
+        self.exec_command(&format!("say {}", crate::quote(message)))
+            .await
+    }
+
+    /// Set the value of a ConVar if it exists, sending the request with a specific ID.
+    ///
+    /// See [`set_value`] and [`set_auto_request_ids`].
+    ///
+    /// [`set_value`]: ClientWrite::set_value
+    /// [`set_auto_request_ids`]: ClientWrite::set_auto_request_ids
+    pub async fn set_value_with_id(
+        &mut self,
+        var: &str,
+        val: &str,
+        request_id: i32,
+    ) -> crate::Result<()> {
+        self.write
+            .send_with_id(Request::SetValue { var, val }, request_id)
+            .await
+    }
+
+    /// Execute a command remotely, sending the request with a specific ID.
+    ///
+    /// See [`exec_command`] and [`set_auto_request_ids`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.exec_command_with_id("map mp_glitch", 1234).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`exec_command`]: ClientWrite::exec_command
+    /// [`set_auto_request_ids`]: ClientWrite::set_auto_request_ids
+    pub async fn exec_command_with_id(&mut self, cmd: &str, request_id: i32) -> crate::Result<()> {
+        self.write
+            .send_with_id(Request::ExecCommand { cmd }, request_id)
+            .await
+    }
+
+    /// Enable or disable automatically numbering requests.
+    ///
+    /// By default every request is sent with an ID of `-1`. When automatic IDs are enabled, each
+    /// request is instead sent with an ID one higher than the last, starting from `0`. The ID a
+    /// request was sent with can be found with [`last_request_id`]. Requests sent with an explicit
+    /// ID, such as with [`exec_command_with_id`], don't affect the numbering.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.set_auto_request_ids(true);
+    ///
+    ///     write.exec_command("status").await.unwrap();
+    ///     println!("Sent status with ID {}", write.last_request_id());
+    /// }
+    /// ```
+    ///
+    /// [`last_request_id`]: ClientWrite::last_request_id
+    /// [`exec_command_with_id`]: ClientWrite::exec_command_with_id
+    pub fn set_auto_request_ids(&mut self, enabled: bool) {
+        self.write.set_auto_request_ids(enabled);
+    }
+
+    /// Enable or disable dry-run mode, where commands and ConVar changes aren't sent.
+    ///
+    /// In dry-run mode, [`exec_command`], [`set_value`] and the methods built on them encode the
+    /// request and return straight away without writing it. With the `tracing` feature, each
+    /// request is logged as an `INFO` event instead. This lets automation be rehearsed against a
+    /// real server. Console logs can still be enabled.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.set_dry_run(true);
+    ///
+    ///     // Not sent
+    ///     write.exec_command("quit").await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`exec_command`]: ClientWrite::exec_command
+    /// [`set_value`]: ClientWrite::set_value
+    pub fn set_dry_run(&mut self, enabled: bool) {
+        self.write.set_dry_run(enabled);
+    }
+
+    /// Check every request against a [`CommandFilter`] before sending it, or stop checking with
+    /// `None`.
+    ///
+    /// Requests the filter denies return
+    /// [`Error::PermissionDenied`](crate::Error::PermissionDenied) without being sent.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, CommandFilter};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.set_command_filter(Some(CommandFilter::new().deny("quit")));
+    ///
+    ///     assert!(write.exec_command("quit").await.is_err());
+    /// }
+    /// ```
+    pub fn set_command_filter(&mut self, filter: Option<CommandFilter>) {
+        self.write.set_filter(filter);
+    }
+
+    /// Get the ID the last request was sent with.
+    ///
+    /// See [`set_auto_request_ids`].
+    ///
+    /// [`set_auto_request_ids`]: ClientWrite::set_auto_request_ids
+    pub fn last_request_id(&self) -> i32 {
+        self.write.last_request_id()
+    }
+
     /// Enable console logs being sent to RCON clients.
     ///
     /// This sets `sv_rcon_sendlogs` to `1`, which will enable logging for all clients until the
@@ -249,6 +585,153 @@ impl ClientWrite {
     pub async fn enable_console_logs(&mut self) -> crate::Result<()> {
         self.write.send(Request::EnableConsoleLogs).await
     }
+
+    /// Execute a command with arguments, each [quoted](crate::quote) so it's read as one argument.
+    ///
+    /// This is for arguments from users, like player names, which could otherwise have quotes or
+    /// `;` in them that change the command being run. Returns
+    /// [`Error::InvalidRequest`](crate::Error::InvalidRequest) without sending anything if the
+    /// command name is invalid, or an argument has a control character other than a line break.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     // Sends `kick "Pilot'; quit"`
+    ///     write.exec_command_args("kick", &["Pilot\"; quit"]).await.unwrap();
+    /// }
+    /// ```
+    pub async fn exec_command_args(&mut self, name: &str, args: &[&str]) -> crate::Result<()> {
+        let command = quoted_command(name, args);
+        self.write.send(command.build()?).await
+    }
+
+    /// Send a request, such as one made with a [`CommandBuilder`](crate::CommandBuilder).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, CommandBuilder};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     let player_id = "2";
+    ///     let command = CommandBuilder::new("kickid").arg(player_id);
+    ///     write.send(command.build().unwrap()).await.unwrap();
+    /// }
+    /// ```
+    pub async fn send(&mut self, request: Request<'_>) -> crate::Result<()> {
+        self.write.send(request).await
+    }
+
+    /// Send a raw protocol request.
+    ///
+    /// This requires the `raw-protocol` feature. See the [`raw`](crate::raw) module.
+    #[cfg(feature = "raw-protocol")]
+    pub async fn send_raw(&mut self, request: crate::raw::Request) -> crate::Result<()> {
+        self.write.send_proto(request).await
+    }
+
+    /// Start holding back requests, so they can be sent together with [`uncork`].
+    ///
+    /// Until `uncork` is called, requests are only added to a buffer, and sending them returns
+    /// straight away. This trades latency for throughput when sending many requests at once, like
+    /// when applying a config.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.cork();
+    ///     write.set_value("ns_private_match_only_host_can_change_settings", "1").await.unwrap();
+    ///     write.set_value("ns_should_return_to_lobby", "0").await.unwrap();
+    ///     write.exec_command("map mp_glitch").await.unwrap();
+    ///
+    ///     // Send all three at once
+    ///     write.uncork().await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`uncork`]: ClientWrite::uncork
+    pub fn cork(&mut self) {
+        self.write.cork();
+    }
+
+    /// Send the requests held back since [`cork`], with as few writes as possible, and go back to
+    /// sending requests straight away.
+    ///
+    /// If this is cancelled, or writing fails, the held back requests that weren't written are
+    /// dropped by the next request sent, but calling `uncork` again sends them. A request that was
+    /// only partly written is always finished first, so the server only sees whole requests.
+    ///
+    /// [`cork`]: ClientWrite::cork
+    pub async fn uncork(&mut self) -> crate::Result<()> {
+        self.write.uncork().await
+    }
+
+    /// Add a request to be sent by [`poll_send`], for use in hand-written [`Future`]
+    /// implementations.
+    ///
+    /// Nothing is written until `poll_send` is called. Requests started this way are kept until
+    /// they've been written, even if another request is sent in the meantime.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, Request};
+    /// use std::future::poll_fn;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.start_send(Request::ExecCommand { cmd: "status" }).unwrap();
+    ///     poll_fn(|cx| write.poll_send(cx)).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`Future`]: std::future::Future
+    /// [`poll_send`]: ClientWrite::poll_send
+    pub fn start_send(&mut self, request: Request<'_>) -> crate::Result<()> {
+        self.write.start_send(request)
+    }
+
+    /// Poll writing the requests added with [`start_send`].
+    ///
+    /// Returns [`Poll::Ready`] once everything has been written. While [corked](ClientWrite::cork)
+    /// this writes anything held back as well.
+    ///
+    /// [`start_send`]: ClientWrite::start_send
+    pub fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        self.write.poll_write_buffer(cx)
+    }
+
+    /// Poll writing the requests added with [`start_send`], and flushing the connection.
+    ///
+    /// [`start_send`]: ClientWrite::start_send
+    pub fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        self.write.poll_flush(cx)
+    }
+
+    /// Poll writing anything queued and then shutting down the write half of the connection.
+    ///
+    /// The server sees the connection as closed, so any requests sent afterwards fail. Console
+    /// logs can still be read from the [`ClientRead`] half until the server closes its side.
+    pub fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        self.write.poll_close(cx)
+    }
 }
 
 impl ClientRead {
@@ -259,7 +742,8 @@ impl ClientRead {
     ///
     /// Log lines are currently buffered, so this function will return lines from the buffer before
     /// waiting for more from the server. This does mean you should always attempt to read logs, to
-    /// avoid the buffer filling up.
+    /// avoid the buffer filling up. Once it's full the server waits until lines are read, unless a
+    /// different [`OverflowPolicy`] is chosen with [`into_buffered`].
     ///
     /// This function does not have a timeout. It will return an error if the connection is closed
     /// or a protocol error occurs, otherwise it will always return a log line.
@@ -284,15 +768,384 @@ impl ClientRead {
     /// ```
     ///
     /// [`ClientWrite::enable_console_logs`]: ClientWrite::enable_console_logs
+    /// [`OverflowPolicy`]: crate::OverflowPolicy
+    /// [`into_buffered`]: ClientRead::into_buffered
     pub async fn receive_console_log(&mut self) -> crate::Result<String> {
         loop {
-            match self.read.receive().await? {
-                Response::Auth { .. } => {
-                    // todo: this should not happen, log an error?
-                    continue;
+            if let Event::ConsoleLog(line) = self.receive_event().await? {
+                return Ok(line);
+            }
+        }
+    }
+
+    /// Receive the next console log line, or response that isn't understood, asynchronously.
+    ///
+    /// Console log lines are handled like with [`receive_console_log`]. Responses this crate
+    /// doesn't understand are skipped unless [`set_unknown_responses`] enables returning them.
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    /// [`set_unknown_responses`]: ClientRead::set_unknown_responses
+    pub async fn receive_event(&mut self) -> crate::Result<Event> {
+        let unknown = self.unknown_responses || self.strict;
+        let line = loop {
+            if let Some(line) = self.dedupe.take_pending() {
+                break line;
+            }
+
+            // Stop waiting when a run of repeated lines needs summarizing, even if nothing else
+            // has arrived
+            let response = match self.dedupe.summary_due_at() {
+                Some(due_at) => {
+                    let receive = receive_response(&mut self.read, unknown);
+                    match tokio::time::timeout_at(due_at.into(), receive).await {
+                        Ok(response) => response?,
+                        Err(_) => break self.dedupe.summarize().unwrap(),
+                    }
+                }
+                None => receive_response(&mut self.read, unknown).await?,
+            };
+
+            match self.response_event(response)? {
+                Some(Event::ConsoleLog(msg)) => {
+                    if let Some(line) = self.dedupe.filter(msg) {
+                        break line;
+                    }
+                }
+                Some(event) => return Ok(event),
+                None => {}
+            }
+        };
+
+        self.history.push(&line);
+        Ok(Event::ConsoleLog(line))
+    }
+
+    /// Poll for the next event, for use in hand-written [`Future`] or `Stream` implementations.
+    ///
+    /// This is the same as [`receive_event`], except repeated lines aren't collapsed, since that
+    /// needs a timer. Lines are still added to the [history](ClientRead::history). If this returns
+    /// [`Poll::Pending`], the task in `cx` is woken once there's more to read.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, Event};
+    /// use std::future::poll_fn;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     loop {
+    ///         if let Event::ConsoleLog(line) = poll_fn(|cx| read.poll_recv(cx)).await.unwrap() {
+    ///             println!("> {}", line);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Future`]: std::future::Future
+    /// [`receive_event`]: ClientRead::receive_event
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<Event>> {
+        let unknown = self.unknown_responses || self.strict;
+        loop {
+            let response = ready!(self.read.poll_receive(cx, unknown))?;
+            match self.response_event(response)? {
+                Some(Event::ConsoleLog(line)) => {
+                    self.history.push(&line);
+                    return Poll::Ready(Ok(Event::ConsoleLog(line)));
+                }
+                Some(event) => return Poll::Ready(Ok(event)),
+                None => {}
+            }
+        }
+    }
+
+    /// Poll for there being something to read, without reading it.
+    ///
+    /// Returns [`Poll::Ready`] once a whole response is buffered or more has arrived from the
+    /// server, including the connection closing. Like Tokio's readiness methods this can be
+    /// spurious: [`poll_recv`] may still return [`Poll::Pending`] if only part of a response has
+    /// arrived, or if what arrived is skipped.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    /// use std::future::poll_fn;
+    /// use std::task::Poll;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     poll_fn(|cx| read.poll_recv_ready(cx)).await.unwrap();
+    ///     let event = poll_fn(|cx| match read.poll_recv(cx) {
+    ///         Poll::Pending => Poll::Ready(None),
+    ///         Poll::Ready(event) => Poll::Ready(Some(event)),
+    ///     })
+    ///     .await;
+    ///     println!("{:?}", event);
+    /// }
+    /// ```
+    ///
+    /// [`poll_recv`]: ClientRead::poll_recv
+    pub fn poll_recv_ready(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        self.read.poll_ready(cx)
+    }
+
+    // The event a response is reported as, if any, or an error for it in strict mode
+    fn response_event(
+        &self,
+        response: Result<Response, northstar_rcon_types::raw::Response>,
+    ) -> crate::Result<Option<Event>> {
+        match response {
+            Ok(Response::Auth { msg, .. }) => {
+                if self.strict {
+                    let msg = format!("authentication response after authenticating: {:?}", msg);
+                    return Err(crate::Error::UnexpectedResponse(msg));
                 }
-                Response::ConsoleLog { msg } => return Ok(msg),
+                Ok(None)
             }
+            Ok(Response::ConsoleLog { msg }) => Ok(Some(Event::ConsoleLog(msg))),
+            Err(unknown) if self.strict => {
+                let msg = format!(
+                    "type {:?} with {:?}",
+                    unknown.responseType.map(|t| t.value()),
+                    unknown.responseBuf
+                );
+                Err(crate::Error::UnexpectedResponse(msg))
+            }
+            Err(unknown) => Ok(Some(Event::Unknown {
+                response_type: unknown.responseType.map(|t| t.value()),
+                payload: unknown.responseBuf,
+            })),
         }
     }
+
+    /// Enable or disable returning responses this crate doesn't understand from
+    /// [`receive_event`], instead of skipping them.
+    ///
+    /// Disabled by default. This is for diagnosing servers that send something new.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, Event};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     read.set_unknown_responses(true);
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     loop {
+    ///         match read.receive_event().await.unwrap() {
+    ///             Event::ConsoleLog(line) => println!("> {}", line),
+    ///             Event::Unknown { response_type, payload } => {
+    ///                 eprintln!("Unknown response {:?}: {:?}", response_type, payload)
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`receive_event`]: ClientRead::receive_event
+    pub fn set_unknown_responses(&mut self, enabled: bool) {
+        self.unknown_responses = enabled;
+    }
+
+    /// Enable or disable strict mode, where responses that would normally be skipped are errors.
+    ///
+    /// In strict mode, [`receive_event`] and [`receive_console_log`] return
+    /// [`Error::UnexpectedResponse`](crate::Error::UnexpectedResponse) for responses this crate
+    /// doesn't understand, including ones missing fields it needs, even with
+    /// [`set_unknown_responses`], and for authentication responses after authenticating. This is
+    /// for integration tests that should fail as soon as a server changes the protocol. Frames
+    /// that can't be decoded are always errors.
+    ///
+    /// Disabled by default. Lines received with [`receive_console_log_bytes`] aren't checked.
+    ///
+    /// [`receive_event`]: ClientRead::receive_event
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    /// [`set_unknown_responses`]: ClientRead::set_unknown_responses
+    /// [`receive_console_log_bytes`]: ClientRead::receive_console_log_bytes
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    /// Receive the next console log line asynchronously, without copying it.
+    ///
+    /// Like [`receive_console_log`], but the line is returned as the bytes the server sent,
+    /// sharing memory with the receive buffer instead of being copied into a new `String`. This
+    /// suits collectors that pass lines on without looking at most of them. The bytes aren't
+    /// checked to be valid UTF-8, so do that with something like [`std::str::from_utf8`] if the
+    /// line is needed as text.
+    ///
+    /// Lines returned by this function are not added to the history, and the duplicate window
+    /// isn't applied to them.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     loop {
+    ///         let line = read.receive_console_log_bytes().await.unwrap();
+    ///         println!("> {}", String::from_utf8_lossy(&line));
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    pub async fn receive_console_log_bytes(&mut self) -> crate::Result<Bytes> {
+        self.read.receive_console_log_bytes().await
+    }
+
+    /// Receive the next raw protocol response asynchronously.
+    ///
+    /// Unlike [`receive_console_log`], this returns every response the server sends, including
+    /// ones this crate doesn't otherwise understand. Console log lines returned by this function
+    /// are not added to the history.
+    ///
+    /// This requires the `raw-protocol` feature. See the [`raw`](crate::raw) module.
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    #[cfg(feature = "raw-protocol")]
+    pub async fn receive_raw(&mut self) -> crate::Result<crate::raw::Response> {
+        self.read.receive_proto().await
+    }
+
+    /// Collapse identical consecutive console log lines into a summary line.
+    ///
+    /// Disabled by default. With a window, a line that's the same as the one before it and
+    /// arrives within the window of it being returned is held back. When a different line
+    /// arrives, or the window passes, [`receive_console_log`] returns
+    /// `last message repeated N times` before carrying on. Passing `None` disables it again.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     read.set_duplicate_window(Some(Duration::from_secs(5)));
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     loop {
+    ///         let line = read.receive_console_log().await.unwrap();
+    ///         println!("> {}", line);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    pub fn set_duplicate_window(&mut self, window: Option<Duration>) {
+        self.dedupe.set_window(window);
+    }
+
+    /// Keep up to this many of the most recently received console log lines.
+    ///
+    /// History is disabled by default. Setting the capacity to `0` disables it again and clears
+    /// any lines already kept. Lines are added to the history as they are returned by
+    /// [`receive_console_log`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     read.set_history_capacity(500);
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     for _ in 0..10 {
+    ///         read.receive_console_log().await.unwrap();
+    ///     }
+    ///
+    ///     for entry in read.history() {
+    ///         println!("{:?}: {}", entry.received_at, entry.line);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`receive_console_log`]: ClientRead::receive_console_log
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history.set_capacity(capacity);
+    }
+
+    /// Get the kept console log lines, from oldest to newest.
+    ///
+    /// See [`set_history_capacity`].
+    ///
+    /// [`set_history_capacity`]: ClientRead::set_history_capacity
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.history.entries()
+    }
+
+    /// Get the kept console log lines received at or after a point in time, from oldest to
+    /// newest.
+    ///
+    /// See [`set_history_capacity`].
+    ///
+    /// [`set_history_capacity`]: ClientRead::set_history_capacity
+    pub fn history_since(&self, since: Instant) -> impl DoubleEndedIterator<Item = &HistoryEntry> {
+        self.history.entries_since(since)
+    }
+
+    /// Get up to `count` of the most recent console log lines, from oldest to newest, such as to
+    /// give context to something that starts reading late.
+    ///
+    /// Servers can't resend old lines, so this replays them from the
+    /// [history](ClientRead::set_history_capacity), which has to be enabled before the lines are
+    /// received. Nothing is sent to the server.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     read.set_history_capacity(500);
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     // ...
+    ///
+    ///     for entry in read.request_log_backfill(50) {
+    ///         println!("{}", entry.line);
+    ///     }
+    /// }
+    /// ```
+    pub fn request_log_backfill(&self, count: usize) -> Vec<HistoryEntry> {
+        let entries = self.history.entries();
+        let skip = entries.len().saturating_sub(count);
+        entries.skip(skip).cloned().collect()
+    }
+}
+
+// Receive the next response, returning ones that aren't understood only if `unknown` is set
+async fn receive_response(
+    read: &mut InnerClientRead,
+    unknown: bool,
+) -> crate::Result<Result<Response, northstar_rcon_types::raw::Response>> {
+    std::future::poll_fn(|cx| read.poll_receive(cx, unknown)).await
 }
@@ -0,0 +1,378 @@
+use crate::Request;
+
+// Characters the server always reads as a token by themselves
+pub(crate) const BREAK_CHARS: &[char] = &['{', '}', '(', ')', '\'', ':'];
+
+/// Quote a command argument so the server reads it as one argument, however it's written.
+///
+/// The server's console has no way to escape a quote inside a quoted argument, so double quotes
+/// are replaced with single quotes. Line breaks would end the command, so they're replaced with
+/// spaces. Anything else, including `;`, is kept as it is.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::quote;
+///
+/// assert_eq!(quote("Restarting; be \"right\" back"), "\"Restarting; be 'right' back\"");
+/// ```
+pub fn quote(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        quoted.push(match c {
+            '"' => '\'',
+            '\r' | '\n' => ' ',
+            c => c,
+        });
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// An error describing why a [`CommandBuilder`] or [`SetValueBuilder`] rejected a request.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidRequest {
+    /// The command or ConVar name is empty.
+    #[error("empty name")]
+    EmptyName,
+
+    /// The command or ConVar name has whitespace, a quote, `;`, or a character the server reads as
+    /// a token by itself, like `(`, in it.
+    #[error("invalid character {0:?} in name")]
+    InvalidName(char),
+
+    /// There's a control character, like a line break or NUL, which could end the command early
+    /// and start another.
+    #[error("control character {0:?}")]
+    ControlCharacter(char),
+
+    /// There's a `;` outside of quotes, which would start another command.
+    #[error("`;` outside quotes")]
+    Separator,
+}
+
+/// Builds a command line that's checked to only run one command.
+///
+/// Arguments are added as they're written with [`arg`], or quoted with [`quoted_arg`], separated
+/// by spaces. [`build`] rejects anything that
+/// would make the server run more than the one command, like line breaks, NULs or a `;` outside
+/// quotes, so arguments from users can't smuggle in commands of their own.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::{CommandBuilder, InvalidRequest, Request};
+///
+/// let command = CommandBuilder::new("kickid").arg("2");
+/// assert!(matches!(command.build(), Ok(Request::ExecCommand { cmd: "kickid 2" })));
+///
+/// let command = CommandBuilder::new("kickid").arg("2; quit");
+/// assert_eq!(command.build().unwrap_err(), InvalidRequest::Separator);
+/// ```
+///
+/// [`arg`]: CommandBuilder::arg
+/// [`build`]: CommandBuilder::build
+/// [`quoted_arg`]: CommandBuilder::quoted_arg
+#[derive(Debug, Clone)]
+pub struct CommandBuilder {
+    line: String,
+    name_len: usize,
+}
+
+impl CommandBuilder {
+    /// Start a command line running the command `name`.
+    pub fn new(name: &str) -> Self {
+        CommandBuilder {
+            line: name.to_string(),
+            name_len: name.len(),
+        }
+    }
+
+    /// Add an argument to the end of the command line.
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.line.push(' ');
+        self.line.push_str(arg);
+        self
+    }
+
+    /// Add an argument to the end of the command line, [quoted](quote) so it's read as one
+    /// argument and anything in it is only ever part of that argument.
+    ///
+    /// # Example
+    /// ```rust
+    /// use northstar_rcon_client::{CommandBuilder, Request};
+    ///
+    /// let command = CommandBuilder::new("kick").quoted_arg("Pilot\"; quit");
+    /// assert!(matches!(command.build(), Ok(Request::ExecCommand { cmd: "kick \"Pilot'; quit\"" })));
+    /// ```
+    pub fn quoted_arg(self, arg: &str) -> Self {
+        self.arg(&quote(arg))
+    }
+
+    /// The command line, which hasn't been checked.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Check the command line, and make a request to run it.
+    pub fn build(&self) -> Result<Request<'_>, InvalidRequest> {
+        check_name(&self.line[..self.name_len])?;
+
+        let mut quoted = false;
+        for c in self.line.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => return Err(InvalidRequest::Separator),
+                c => check_char(c)?,
+            }
+        }
+        Ok(Request::ExecCommand { cmd: &self.line })
+    }
+}
+
+// The command line `ClientWrite::exec_command_args` sends
+pub(crate) fn quoted_command(name: &str, args: &[&str]) -> CommandBuilder {
+    args.iter().fold(CommandBuilder::new(name), |command, arg| {
+        command.quoted_arg(arg)
+    })
+}
+
+/// Builds a request to set a ConVar, checked to not have anything that could run commands.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::{InvalidRequest, SetValueBuilder};
+///
+/// assert!(SetValueBuilder::new("ns_server_name").value("My Server").build().is_ok());
+///
+/// let set_value = SetValueBuilder::new("ns_server_name").value("My Server\nquit");
+/// assert_eq!(set_value.build().unwrap_err(), InvalidRequest::ControlCharacter('\n'));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SetValueBuilder {
+    var: String,
+    val: String,
+}
+
+impl SetValueBuilder {
+    /// Start a request to set the ConVar `var`, to an empty value until [`value`] is called.
+    ///
+    /// [`value`]: SetValueBuilder::value
+    pub fn new(var: &str) -> Self {
+        SetValueBuilder {
+            var: var.to_string(),
+            val: String::new(),
+        }
+    }
+
+    /// Set the value to set the ConVar to.
+    pub fn value(mut self, val: &str) -> Self {
+        self.val = val.to_string();
+        self
+    }
+
+    /// Check the ConVar name and value, and make a request to set it.
+    pub fn build(&self) -> Result<Request<'_>, InvalidRequest> {
+        check_name(&self.var)?;
+        self.val.chars().try_for_each(check_char)?;
+        Ok(Request::SetValue {
+            var: &self.var,
+            val: &self.val,
+        })
+    }
+}
+
+fn check_name(name: &str) -> Result<(), InvalidRequest> {
+    if name.is_empty() {
+        return Err(InvalidRequest::EmptyName);
+    }
+    for c in name.chars() {
+        check_char(c)?;
+        if c.is_whitespace() || c == '"' || c == ';' || BREAK_CHARS.contains(&c) {
+            return Err(InvalidRequest::InvalidName(c));
+        }
+    }
+    Ok(())
+}
+
+// Tabs are only ever whitespace to the server
+fn check_char(c: char) -> Result<(), InvalidRequest> {
+    if c.is_control() && c != '\t' {
+        return Err(InvalidRequest::ControlCharacter(c));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Check a whole command line, with the name up to the first space
+    fn check(line: &str) -> Result<(), InvalidRequest> {
+        let builder = CommandBuilder {
+            line: line.to_string(),
+            name_len: line.find(' ').unwrap_or(line.len()),
+        };
+        builder.build().map(drop)
+    }
+
+    #[test]
+    fn quoted_command_args() {
+        let command = quoted_command("kick", &["Pilot\"; quit", "a\r\nb", "(x)'{}"]);
+        assert!(matches!(
+            command.build(),
+            Ok(Request::ExecCommand {
+                cmd: "kick \"Pilot'; quit\" \"a  b\" \"(x)'{}\""
+            })
+        ));
+        assert!(matches!(
+            quoted_command("status", &[]).build(),
+            Ok(Request::ExecCommand { cmd: "status" })
+        ));
+
+        let command = quoted_command("kick", &["Pilot\0quit"]);
+        assert_eq!(
+            command.build().unwrap_err(),
+            InvalidRequest::ControlCharacter('\0')
+        );
+
+        assert_eq!(
+            quoted_command("", &["quit"]).build().unwrap_err(),
+            InvalidRequest::EmptyName
+        );
+        assert_eq!(
+            quoted_command("kick;quit", &["x"]).build().unwrap_err(),
+            InvalidRequest::InvalidName(';')
+        );
+        assert_eq!(
+            quoted_command("quit'", &["x"]).build().unwrap_err(),
+            InvalidRequest::InvalidName('\'')
+        );
+    }
+
+    #[test]
+    fn quote_keeps_one_argument() {
+        assert_eq!(quote(""), "\"\"");
+        assert_eq!(quote("a; b"), "\"a; b\"");
+        assert_eq!(quote("\"quit\""), "\"'quit'\"");
+        assert_eq!(quote("a\r\nb\nc"), "\"a  b c\"");
+        assert_eq!(quote("(x)"), "\"(x)\"");
+    }
+
+    #[test]
+    fn control_characters() {
+        for c in ['\0', '\r', '\n', '\x1b', '\u{85}'] {
+            let line = format!("say hi{}quit", c);
+            assert_eq!(check(&line), Err(InvalidRequest::ControlCharacter(c)));
+
+            let set_value = SetValueBuilder::new("hostname").value(&line);
+            assert_eq!(
+                set_value.build().unwrap_err(),
+                InvalidRequest::ControlCharacter(c)
+            );
+        }
+
+        // Quoting doesn't help, since a line break ends the command anyway
+        let line = "say \"hi\0quit\"";
+        assert_eq!(check(line), Err(InvalidRequest::ControlCharacter('\0')));
+
+        assert!(check("say a\tb").is_ok());
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(check("say hi; quit"), Err(InvalidRequest::Separator));
+        assert_eq!(check("say hi;"), Err(InvalidRequest::Separator));
+        assert!(check("say \"hi; quit\"").is_ok());
+
+        // A `;` after a quoted argument is outside quotes again
+        assert_eq!(check("say \"hi\"; quit"), Err(InvalidRequest::Separator));
+        assert_eq!(
+            check("say \"a\" b \"c\";quit"),
+            Err(InvalidRequest::Separator)
+        );
+
+        // ConVar values aren't split into commands
+        assert!(SetValueBuilder::new("hostname")
+            .value("a; b")
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn unbalanced_quotes() {
+        // The server reads an unclosed quote to the end of the line, so this is one argument
+        assert!(check("say \"hi; quit").is_ok());
+
+        // Quoting after an unbalanced quote closes it and opens another, which is left open
+        let builder = CommandBuilder::new("say")
+            .arg("\"")
+            .quoted_arg("x")
+            .arg("; quit");
+        assert!(builder.build().is_ok());
+
+        // Here the quoted argument's own quotes are flipped, leaving its `;` outside
+        let builder = CommandBuilder::new("say").arg("\"").quoted_arg("x; quit");
+        assert_eq!(builder.build().unwrap_err(), InvalidRequest::Separator);
+    }
+
+    #[test]
+    fn break_characters() {
+        for c in BREAK_CHARS.iter().copied() {
+            let name = format!("quit{}", c);
+            let builder = CommandBuilder::new(&name).arg("x");
+            assert_eq!(builder.build().unwrap_err(), InvalidRequest::InvalidName(c));
+
+            let set_value = SetValueBuilder::new(&name).value("1");
+            assert_eq!(
+                set_value.build().unwrap_err(),
+                InvalidRequest::InvalidName(c)
+            );
+
+            // They're only ever part of an argument after the name
+            let arg = format!("{}x{}", c, c);
+            assert!(CommandBuilder::new("say").arg(&arg).build().is_ok());
+            assert!(CommandBuilder::new("say").quoted_arg(&arg).build().is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert_eq!(
+            CommandBuilder::new("").arg("quit").build().unwrap_err(),
+            InvalidRequest::EmptyName
+        );
+        assert_eq!(
+            SetValueBuilder::new("").value("1").build().unwrap_err(),
+            InvalidRequest::EmptyName
+        );
+
+        for (name, c) in [
+            ("kick id", ' '),
+            ("kick\tid", '\t'),
+            ("\"quit\"", '"'),
+            ("quit;", ';'),
+            ("quit\u{a0}", '\u{a0}'),
+        ] {
+            let builder = CommandBuilder::new(name).arg("2");
+            assert_eq!(builder.build().unwrap_err(), InvalidRequest::InvalidName(c));
+        }
+
+        let builder = CommandBuilder::new("quit\n").arg("2");
+        assert_eq!(
+            builder.build().unwrap_err(),
+            InvalidRequest::ControlCharacter('\n')
+        );
+
+        assert!(matches!(
+            CommandBuilder::new("kickid").arg("2").build(),
+            Ok(Request::ExecCommand { cmd: "kickid 2" })
+        ));
+        assert!(matches!(
+            SetValueBuilder::new("sv_cheats").value("1").build(),
+            Ok(Request::SetValue {
+                var: "sv_cheats",
+                val: "1"
+            })
+        ));
+    }
+}
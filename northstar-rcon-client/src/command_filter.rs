@@ -0,0 +1,169 @@
+use crate::scoped::command_names;
+use crate::Request;
+
+/// Allow and deny lists of command and ConVar names, for stopping requests before they're sent.
+///
+/// Patterns are globs matched against whole names ignoring case, where `*` matches any run of
+/// characters and `?` matches any one. Regular expressions aren't supported. A name is denied if it matches a deny pattern, or if there
+/// are allow patterns and it matches none of them. Like with [`ScopedClient`], each command in a
+/// command line is checked, and setting a ConVar checks its name.
+///
+/// A filter can be installed on a writer with [`ClientWrite::set_command_filter`], or before
+/// connecting with [`NotAuthenticatedClient::command_filter`] or
+/// [`ReconnectingClient::command_filter`]. Other wrappers can check requests themselves with
+/// [`check`](CommandFilter::check).
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::{CommandFilter, Error, Request};
+///
+/// let filter = CommandFilter::new().deny("quit").deny("sv_*");
+///
+/// assert!(filter.check(&Request::ExecCommand { cmd: "map mp_glitch" }).is_ok());
+/// assert!(matches!(
+///     filter.check(&Request::ExecCommand { cmd: "say bye; QUIT" }),
+///     Err(Error::PermissionDenied(name)) if name == "QUIT"
+/// ));
+/// assert!(filter
+///     .check(&Request::SetValue { var: "sv_cheats", val: "1" })
+///     .is_err());
+/// ```
+///
+/// [`ScopedClient`]: crate::ScopedClient
+/// [`ClientWrite::set_command_filter`]: crate::ClientWrite::set_command_filter
+/// [`NotAuthenticatedClient::command_filter`]: crate::NotAuthenticatedClient::command_filter
+/// [`ReconnectingClient::command_filter`]: crate::ReconnectingClient::command_filter
+#[derive(Debug, Clone, Default)]
+pub struct CommandFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl CommandFilter {
+    /// Create a filter that allows everything.
+    pub fn new() -> Self {
+        CommandFilter::default()
+    }
+
+    /// Only allow names matching this pattern, or any other allow pattern.
+    pub fn allow(mut self, pattern: &str) -> Self {
+        self.allow.push(pattern.to_lowercase());
+        self
+    }
+
+    /// Deny names matching this pattern, even if they're allowed.
+    pub fn deny(mut self, pattern: &str) -> Self {
+        self.deny.push(pattern.to_lowercase());
+        self
+    }
+
+    /// Whether a command or ConVar name passes the filter.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        let name: Vec<char> = name.to_lowercase().chars().collect();
+        let matches = |pattern: &String| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            glob_matches(&pattern, &name)
+        };
+
+        !self.deny.iter().any(matches) && (self.allow.is_empty() || self.allow.iter().any(matches))
+    }
+
+    /// Check a request, returning [`Error::PermissionDenied`](crate::Error::PermissionDenied)
+    /// with the first name that's denied. Enabling console logs and authenticating are always
+    /// allowed.
+    pub fn check(&self, request: &Request<'_>) -> crate::Result<()> {
+        let denied = match *request {
+            Request::ExecCommand { cmd } => command_names(cmd).find(|name| !self.is_allowed(name)),
+            Request::SetValue { var, .. } => Some(var).filter(|var| !self.is_allowed(var)),
+            Request::Auth { .. } | Request::EnableConsoleLogs => None,
+        };
+
+        match denied {
+            Some(name) => Err(crate::Error::PermissionDenied(name.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+// Match a whole name against a glob, backtracking to the last `*` on a mismatch
+fn glob_matches(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CommandFilter, Error, Request};
+
+    fn denied(filter: &CommandFilter, cmd: &str) -> Option<String> {
+        match filter.check(&Request::ExecCommand { cmd }) {
+            Ok(()) => None,
+            Err(Error::PermissionDenied(name)) => Some(name),
+            Err(err) => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn globs() {
+        let filter = CommandFilter::new().deny("sv_*").deny("ki?k");
+        assert!(!filter.is_allowed("sv_cheats"));
+        assert!(!filter.is_allowed("SV_"));
+        assert!(!filter.is_allowed("kick"));
+        assert!(filter.is_allowed("kickid"));
+        assert!(filter.is_allowed("mp_sv_x"));
+    }
+
+    #[test]
+    fn filter_denies_bypasses() {
+        let filter = CommandFilter::new().deny("quit").deny("ban");
+        for cmd in [
+            "quit",
+            "QUIT",
+            " quit",
+            "quit'",
+            "quit(",
+            "quit{}",
+            "quit\x01",
+            "\"quit\"",
+            "say hi;quit",
+            "say hi\nquit",
+            "say \"hi\nquit",
+        ] {
+            let name = denied(&filter, cmd).map(|name| name.to_lowercase());
+            assert_eq!(name.as_deref(), Some("quit"), "{:?}", cmd);
+        }
+        assert_eq!(denied(&filter, "ban(x)").as_deref(), Some("ban"));
+        assert_eq!(denied(&filter, "say \"hi; quit\""), None);
+        assert_eq!(denied(&filter, "quitter"), None);
+    }
+
+    #[test]
+    fn allow_list_rejects_break_characters() {
+        let filter = CommandFilter::new().allow("say");
+        assert_eq!(denied(&filter, "say hi"), None);
+        assert_eq!(denied(&filter, "(say"), Some("(".to_string()));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::status::strip_tags;
+use crate::{ClientRead, ClientWrite};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// What the server prints when there's no command or ConVar with the name, where `{}` is the
+/// name.
+const UNKNOWN_MESSAGE: &str = "Unknown command \"{}\"";
+
+/// What the server prints when a ConVar can't be changed, where `{}` is its name.
+const READ_ONLY_MESSAGES: &[&str] = &[
+    "Can't set {} in multiplayer",
+    "Can't use cheat cvar {} in multiplayer, unless the server has sv_cheats set to 1.",
+    "Can't change replicated ConVar {} from console of client, only server operator can change its value",
+];
+
+/// An error describing why a ConVar couldn't be set, from
+/// [`ClientRead::set_value_checked`].
+#[derive(Debug, thiserror::Error)]
+pub enum SetValueError {
+    /// The server has no ConVar with the name.
+    #[error("unknown ConVar")]
+    UnknownConVar,
+
+    /// The ConVar can't be changed, such as because it's protected by `sv_cheats`.
+    #[error("read-only ConVar: {message}")]
+    ReadOnly {
+        /// The line the server printed about it.
+        message: String,
+    },
+
+    /// The request couldn't be sent, or the console logs couldn't be read.
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+impl SetValueError {
+    /// Read the error a console log line reports about setting `var`, if it's about that.
+    ///
+    /// Only the messages the server prints are recognized, with `var` as the whole name in them,
+    /// so a line about a similarly named ConVar or a player talking about `var` isn't mistaken
+    /// for an error. Names are compared ignoring case, like the server does.
+    ///
+    /// # Example
+    /// ```rust
+    /// use northstar_rcon_client::SetValueError;
+    ///
+    /// let err = SetValueError::from_line("ns_nope", "Unknown command \"ns_nope\"");
+    /// assert!(matches!(err, Some(SetValueError::UnknownConVar)));
+    /// assert!(SetValueError::from_line("ns_nope", "Unknown command \"other\"").is_none());
+    /// ```
+    pub fn from_line(var: &str, line: &str) -> Option<SetValueError> {
+        let line = strip_tags(line).trim_end();
+        if is_message(line, UNKNOWN_MESSAGE, var) {
+            return Some(SetValueError::UnknownConVar);
+        }
+        if READ_ONLY_MESSAGES
+            .iter()
+            .any(|message| is_message(line, message, var))
+        {
+            return Some(SetValueError::ReadOnly {
+                message: line.to_string(),
+            });
+        }
+        None
+    }
+}
+
+// Whether `line` is `message` with `{}` replaced by `var`, ignoring ASCII case
+fn is_message(line: &str, message: &str, var: &str) -> bool {
+    let (prefix, suffix) = message.split_once("{}").unwrap();
+    if line.len() != prefix.len() + var.len() + suffix.len() {
+        return false;
+    }
+    let name_end = line.len() - suffix.len();
+    [
+        (0, prefix.len(), prefix),
+        (prefix.len(), name_end, var),
+        (name_end, line.len(), suffix),
+    ]
+    .into_iter()
+    .all(|(start, end, expected)| {
+        line.get(start..end)
+            .is_some_and(|part| part.eq_ignore_ascii_case(expected))
+    })
+}
+
+impl ClientRead {
+    /// Set the value of a ConVar with `write`, and watch the console logs for `wait` afterwards
+    /// for the server saying it couldn't be set.
+    ///
+    /// The server doesn't respond to setting a ConVar, and only prints a line if it fails, so
+    /// this returns `Ok` if nothing is printed in time. Console logs need to be enabled. Other
+    /// lines received while waiting are skipped, though they're still kept in the
+    /// [history](ClientRead::history).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, SetValueError};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     let wait = Duration::from_millis(500);
+    ///     match read.set_value_checked(&mut write, "sv_cheats", "1", wait).await {
+    ///         Ok(()) => println!("Set"),
+    ///         Err(SetValueError::UnknownConVar) => println!("No such ConVar"),
+    ///         Err(err) => println!("Couldn't set it: {}", err),
+    ///     }
+    /// }
+    /// ```
+    pub async fn set_value_checked(
+        &mut self,
+        write: &mut ClientWrite,
+        var: &str,
+        val: &str,
+        wait: Duration,
+    ) -> Result<(), SetValueError> {
+        write.set_value(var, val).await?;
+
+        let deadline = Instant::now() + wait;
+        loop {
+            let line = match tokio::time::timeout_at(deadline, self.receive_console_log()).await {
+                Ok(line) => line?,
+                Err(_) => return Ok(()),
+            };
+            if let Some(err) = SetValueError::from_line(var, &line) {
+                return Err(err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_convar() {
+        let line = "Unknown command \"ns_nope\"";
+        assert!(matches!(
+            SetValueError::from_line("ns_nope", line),
+            Some(SetValueError::UnknownConVar)
+        ));
+        assert!(matches!(
+            SetValueError::from_line("NS_Nope", "[ENGINE SV] unknown command \"ns_nope\"\n"),
+            Some(SetValueError::UnknownConVar)
+        ));
+
+        // Other names containing this one, or contained in it
+        assert!(SetValueError::from_line("ns_nope", "Unknown command \"ns_nope2\"").is_none());
+        assert!(SetValueError::from_line("ns_nope2", line).is_none());
+        assert!(SetValueError::from_line("nope", line).is_none());
+    }
+
+    #[test]
+    fn read_only_convar() {
+        let line = "Can't use cheat cvar sv_gravity in multiplayer, unless the server has \
+                    sv_cheats set to 1.";
+        let Some(SetValueError::ReadOnly { message }) =
+            SetValueError::from_line("sv_gravity", line)
+        else {
+            panic!("expected a read-only error");
+        };
+        assert_eq!(message, line);
+
+        assert!(matches!(
+            SetValueError::from_line("sv_cheats", "Can't set sv_cheats in multiplayer"),
+            Some(SetValueError::ReadOnly { .. })
+        ));
+        assert!(
+            SetValueError::from_line("sv_cheats", "Can't set sv_cheats2 in multiplayer").is_none()
+        );
+    }
+
+    #[test]
+    fn unrelated_lines() {
+        for line in [
+            "can't set sv_cheats lol",
+            "Pilot: Unknown command \"sv_cheats\"",
+            "[SCRIPT SV] [CHAT] Pilot: sv_cheats is read only",
+            "sv_cheats = 0",
+            "",
+        ] {
+            assert!(
+                SetValueError::from_line("sv_cheats", line).is_none(),
+                "{:?}",
+                line
+            );
+        }
+    }
+}
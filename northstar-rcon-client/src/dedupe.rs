@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+/// Collapses runs of identical console log lines into a single summary line.
+///
+/// See [`ClientRead::set_duplicate_window`].
+///
+/// [`ClientRead::set_duplicate_window`]: crate::ClientRead::set_duplicate_window
+#[derive(Debug, Default)]
+pub(crate) struct Dedupe {
+    window: Option<Duration>,
+    last: Option<String>,
+    last_shown_at: Option<Instant>,
+    repeats: usize,
+    pending: Option<String>,
+}
+
+impl Dedupe {
+    pub fn set_window(&mut self, window: Option<Duration>) {
+        // Repeats held back so far are forgotten when disabling, since there's no run to end
+        if window.is_none() {
+            self.last = None;
+            self.repeats = 0;
+        }
+        self.window = window;
+    }
+
+    /// A line that was held back to return after a summary.
+    pub fn take_pending(&mut self) -> Option<String> {
+        self.pending.take()
+    }
+
+    /// When the current run of repeated lines needs to be summarized, if there is one.
+    pub fn summary_due_at(&self) -> Option<Instant> {
+        match (self.window, self.last_shown_at) {
+            (Some(window), Some(shown_at)) if self.repeats > 0 => Some(shown_at + window),
+            _ => None,
+        }
+    }
+
+    /// Pass a received line through, returning the line to return to the caller and holding any
+    /// line that has to wait until after a summary. Returns `None` if the line was a repeat.
+    pub fn filter(&mut self, line: String) -> Option<String> {
+        let window = match self.window {
+            Some(window) => window,
+            None => return Some(line),
+        };
+
+        let is_repeat = self.last.as_ref() == Some(&line)
+            && self
+                .last_shown_at
+                .is_some_and(|shown_at| shown_at.elapsed() < window);
+        if is_repeat {
+            self.repeats += 1;
+            return None;
+        }
+
+        let summary = self.summarize();
+        self.last = Some(line.clone());
+        self.last_shown_at = Some(Instant::now());
+
+        match summary {
+            Some(summary) => {
+                self.pending = Some(line);
+                Some(summary)
+            }
+            None => Some(line),
+        }
+    }
+
+    /// End the current run of repeated lines, returning its summary if any lines were repeated.
+    pub fn summarize(&mut self) -> Option<String> {
+        let repeats = std::mem::take(&mut self.repeats);
+        match repeats {
+            0 => None,
+            1 => Some("last message repeated 1 time".to_string()),
+            _ => Some(format!("last message repeated {} times", repeats)),
+        }
+    }
+}
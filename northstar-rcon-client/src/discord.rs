@@ -0,0 +1,205 @@
+//! Relay console logs to a Discord channel through a webhook.
+//!
+//! This module requires the `discord` feature.
+
+use crate::{ClientRead, LogLevel};
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+// Discord rejects messages with more characters than this.
+const MAX_MESSAGE_LEN: usize = 2000;
+
+// Characters used to wrap each message in a code block.
+const CODE_BLOCK_START: &str = "```\n";
+const CODE_BLOCK_END: &str = "```";
+
+// Backticks in lines are replaced with this lookalike, so they can't end the code block
+const BACKTICK_REPLACEMENT: &str = "\u{2CB}";
+
+/// An error describing why relaying logs to Discord failed.
+#[derive(Debug, thiserror::Error)]
+pub enum DiscordError {
+    /// The webhook request failed, or the webhook returned an error status.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// Reading logs from the RCON server failed.
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+/// Batches console log lines and posts them to a Discord webhook.
+///
+/// Lines are collected as they are received and posted together at a fixed interval, which keeps
+/// the relay well within Discord's webhook rate limits. If Discord asks the relay to slow down
+/// anyway, it will wait for as long as requested before trying again.
+///
+/// Lines below a minimum level are dropped. Lines without a level are treated as
+/// [`LogLevel::Info`].
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::connect;
+/// use northstar_rcon_client::discord::DiscordRelay;
+/// use northstar_rcon_client::LogLevel;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+///
+///     write.enable_console_logs().await.unwrap();
+///
+///     DiscordRelay::new("https://discord.com/api/webhooks/123/abc")
+///         .min_level(LogLevel::Warning)
+///         .run(&mut read)
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DiscordRelay {
+    http: reqwest::Client,
+    url: String,
+    min_level: LogLevel,
+    interval: Duration,
+    pending: Vec<String>,
+}
+
+impl DiscordRelay {
+    /// Create a relay that posts to a webhook URL.
+    ///
+    /// By default all lines at [`LogLevel::Info`] or above are relayed, and batches are posted
+    /// every two seconds.
+    pub fn new(url: impl Into<String>) -> Self {
+        DiscordRelay {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            min_level: LogLevel::Info,
+            interval: Duration::from_secs(2),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Only relay lines at or above this level.
+    pub fn min_level(mut self, level: LogLevel) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Set how often batches of lines are posted.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Queue a line to be posted with the next batch, if it passes the level filter.
+    pub fn push(&mut self, line: &str) {
+        let level = LogLevel::parse(line).unwrap_or(LogLevel::Info);
+        if level >= self.min_level {
+            self.pending.push(line.to_string());
+        }
+    }
+
+    /// Post all queued lines to the webhook immediately.
+    ///
+    /// Lines are split into as few messages as possible. Lines that don't fit in a single message
+    /// are truncated. Backticks in lines are replaced with a lookalike character, so lines can't
+    /// end the code block they're posted in, and messages never ping anyone.
+    pub async fn flush(&mut self) -> Result<(), DiscordError> {
+        let lines = std::mem::take(&mut self.pending);
+        let max_content_len = MAX_MESSAGE_LEN - CODE_BLOCK_START.len() - CODE_BLOCK_END.len();
+
+        let mut content = String::new();
+        for line in &lines {
+            let line = escape_line(line);
+            let line = truncate_to_char_boundary(&line, max_content_len - 1);
+
+            if content.len() + line.len() + 1 > max_content_len {
+                self.post(&content).await?;
+                content.clear();
+            }
+
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        if !content.is_empty() {
+            self.post(&content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Relay lines from a client until the connection fails.
+    ///
+    /// Console logs must be enabled separately, for example with
+    /// [`ClientWrite::enable_console_logs`].
+    ///
+    /// [`ClientWrite::enable_console_logs`]: crate::ClientWrite::enable_console_logs
+    pub async fn run(mut self, read: &mut ClientRead) -> Result<(), DiscordError> {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                line = read.receive_console_log() => self.push(&line?),
+                _ = ticker.tick() => self.flush().await?,
+            }
+        }
+    }
+
+    async fn post(&self, content: &str) -> Result<(), DiscordError> {
+        let body = serde_json::json!({
+            "content": format!("{}{}{}", CODE_BLOCK_START, content, CODE_BLOCK_END),
+            // Lines can come from players, who mustn't be able to mention everyone or a role
+            "allowed_mentions": { "parse": [] },
+        });
+
+        loop {
+            let response = self.http.post(&self.url).json(&body).send().await?;
+
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                let rate_limit: serde_json::Value = response.json().await?;
+                let retry_after = rate_limit["retry_after"].as_f64().unwrap_or(1.0);
+                tokio::time::sleep(Duration::from_secs_f64(retry_after)).await;
+                continue;
+            }
+
+            response.error_for_status()?;
+            return Ok(());
+        }
+    }
+}
+
+// Prepare a line to be put in the code block
+fn escape_line(line: &str) -> String {
+    line.trim_end().replace('`', BACKTICK_REPLACEMENT)
+}
+
+fn truncate_to_char_boundary(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_line;
+
+    #[test]
+    fn lines_cant_end_the_code_block() {
+        let line = escape_line("[CHAT] Pilot: ```\n@everyone ```\n");
+        assert!(!line.contains('`'));
+        assert_eq!(
+            line,
+            "[CHAT] Pilot: \u{2CB}\u{2CB}\u{2CB}\n@everyone \u{2CB}\u{2CB}\u{2CB}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::status::strip_tags;
+use std::collections::VecDeque;
+
+/// How many sent commands a [`CommandEcho`] waits for the echo of before forgetting the oldest.
+const MAX_PENDING: usize = 64;
+
+/// Matches the server's console echo of each command back to the command sent, and tags the
+/// output lines that follow with it.
+///
+/// When the server runs a command it prints it first, like `] status`. Each command this client
+/// sends should be passed to [`sent`](CommandEcho::sent), and every console log line to
+/// [`observe`](CommandEcho::observe), which returns the command the line came from. A line is
+/// tagged with the last of this client's commands to be echoed, until anything else is echoed,
+/// such as a command sent by another client.
+///
+/// Commands are matched ignoring case and surrounding whitespace. Output printed by the server
+/// on its own between two commands, without an echo first, is still tagged with the earlier
+/// command.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::CommandEcho;
+///
+/// let mut echo = CommandEcho::new();
+/// echo.sent("status");
+///
+/// assert_eq!(echo.observe("] status"), Some("status"));
+/// assert_eq!(echo.observe("hostname: Northstar Server"), Some("status"));
+///
+/// // Someone else ran a command
+/// assert_eq!(echo.observe("] kickid 2"), None);
+/// assert_eq!(echo.observe("Kicked player 2"), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CommandEcho {
+    prefix: String,
+    pending: VecDeque<String>,
+    current: Option<String>,
+}
+
+impl CommandEcho {
+    /// Create a matcher for echoes starting with `] `.
+    pub fn new() -> Self {
+        CommandEcho {
+            prefix: "] ".to_string(),
+            pending: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    /// Set what echoed commands start with, for servers that print them differently.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Record a command line sent by this client, so its echo can be recognized.
+    pub fn sent(&mut self, cmd: &str) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(cmd.trim().to_string());
+    }
+
+    /// The command that's being tagged, if any.
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Read a console log line, returning the command it's the echo or output of, if it's one of
+    /// this client's.
+    pub fn observe(&mut self, line: &str) -> Option<&str> {
+        if let Some(echoed) = strip_tags(line).strip_prefix(self.prefix.as_str()) {
+            let echoed = echoed.trim();
+
+            // Commands are run in order, so any still waiting before this one were missed
+            let position = self
+                .pending
+                .iter()
+                .position(|cmd| cmd.eq_ignore_ascii_case(echoed));
+            self.current = match position {
+                Some(index) => {
+                    self.pending.drain(..index);
+                    self.pending.pop_front()
+                }
+                None => None,
+            };
+        }
+        self.current()
+    }
+}
+
+impl Default for CommandEcho {
+    fn default() -> Self {
+        CommandEcho::new()
+    }
+}
@@ -0,0 +1,198 @@
+//! Write console logs to a file with rotation.
+//!
+//! This module requires the `file-sink` feature.
+
+use crate::ClientRead;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// When a [`FileSink`] starts a new file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// Never rotate, the file grows forever.
+    Never,
+
+    /// Rotate once the file would grow beyond this many bytes.
+    Size(u64),
+
+    /// Rotate once the file has been open for this long.
+    Interval(Duration),
+}
+
+/// Writes console log lines to a file, rotating it by size or age.
+///
+/// When the file is rotated it's renamed to `<path>.1`, with older files shifted to `<path>.2`,
+/// `<path>.3` and so on, up to a maximum number of files. Rotated files can optionally be
+/// compressed with gzip, in which case `.gz` is appended to their names.
+///
+/// Every line is flushed to the operating system as soon as it's written, so a crash of the
+/// process won't lose logs. Files are also synced to disk before being rotated.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::connect;
+/// use northstar_rcon_client::file_sink::{FileSink, Rotation};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+///
+///     write.enable_console_logs().await.unwrap();
+///
+///     FileSink::open("server.log")
+///         .unwrap()
+///         .rotation(Rotation::Size(10 * 1024 * 1024))
+///         .compress(true)
+///         .run(&mut read)
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    file: BufWriter<File>,
+    rotation: Rotation,
+    max_files: usize,
+    compress: bool,
+    file_len: u64,
+    opened_at: Instant,
+}
+
+impl FileSink {
+    /// Open a file to write logs to, appending to it if it already exists.
+    ///
+    /// By default the file is never rotated. If rotation is enabled, 5 rotated files are kept
+    /// without compression.
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        let file_len = file.metadata()?.len();
+
+        Ok(FileSink {
+            path,
+            file: BufWriter::new(file),
+            rotation: Rotation::Never,
+            max_files: 5,
+            compress: false,
+            file_len,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// Set when the file is rotated.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set how many rotated files are kept. Older files are deleted.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Set whether rotated files are compressed with gzip.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Write a single console log line, rotating the file first if needed.
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let line = line.trim_end();
+        let line_len = line.len() as u64 + 1;
+
+        let should_rotate = match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(max_len) => self.file_len > 0 && self.file_len + line_len > max_len,
+            Rotation::Interval(interval) => self.opened_at.elapsed() >= interval,
+        };
+        if should_rotate {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        self.file_len += line_len;
+
+        Ok(())
+    }
+
+    /// Rotate the file immediately.
+    pub fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_all()?;
+
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            // Shift existing files up by one, dropping the oldest
+            remove_if_exists(&self.rotated_path(self.max_files))?;
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            if self.compress {
+                compress_file(&self.path, &self.rotated_path(1))?;
+                std::fs::remove_file(&self.path)?;
+            } else {
+                std::fs::rename(&self.path, self.rotated_path(1))?;
+            }
+        }
+
+        self.file = BufWriter::new(open_append(&self.path)?);
+        self.file_len = 0;
+        self.opened_at = Instant::now();
+
+        Ok(())
+    }
+
+    /// Write lines from a client until the connection or a write fails.
+    ///
+    /// Console logs must be enabled separately, for example with
+    /// [`ClientWrite::enable_console_logs`].
+    ///
+    /// [`ClientWrite::enable_console_logs`]: crate::ClientWrite::enable_console_logs
+    pub async fn run(mut self, read: &mut ClientRead) -> crate::Result<()> {
+        loop {
+            let line = read.receive_console_log().await?;
+            self.write_line(&line)?;
+        }
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        if self.compress {
+            path.push(".gz");
+        }
+        path.into()
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn compress_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut input = File::open(from)?;
+    let mut encoder = GzEncoder::new(File::create(to)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()
+}
@@ -0,0 +1,110 @@
+use crate::ManagerError;
+use std::fmt::{Display, Formatter};
+
+/// Whether an operation worked on each of many servers, such as from
+/// [`ServerManager::exec_command_all`](crate::ServerManager::exec_command_all).
+///
+/// Results are kept in the order they were added. Formatting with `{}` writes one line per server,
+/// like `eu-1: done` or `us-1: timed out`.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::{FleetResult, ManagerError};
+///
+/// let results: FleetResult<&str> = [
+///     ("eu-1", Ok(())),
+///     ("us-1", Err(ManagerError::TimedOut)),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert!(!results.all_ok());
+/// assert_eq!(results.failures().count(), 1);
+/// assert_eq!(results.to_string(), "eu-1: done\nus-1: timed out");
+/// ```
+#[derive(Debug)]
+pub struct FleetResult<L, E = ManagerError> {
+    results: Vec<(L, Result<(), E>)>,
+}
+
+impl<L, E> FleetResult<L, E> {
+    /// Whether the operation worked on every server. This is `true` if there were no servers.
+    pub fn all_ok(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// The servers the operation worked on.
+    pub fn successes(&self) -> impl Iterator<Item = &L> {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(label, _)| label)
+    }
+
+    /// The servers the operation failed on, with why.
+    pub fn failures(&self) -> impl Iterator<Item = (&L, &E)> {
+        self.results
+            .iter()
+            .filter_map(|(label, result)| result.as_ref().err().map(|err| (label, err)))
+    }
+
+    /// Split into the servers the operation worked on, and the ones it failed on with why.
+    pub fn partition(self) -> (Vec<L>, Vec<(L, E)>) {
+        let mut successes = Vec::new();
+        let mut failures = Vec::new();
+        for (label, result) in self.results {
+            match result {
+                Ok(()) => successes.push(label),
+                Err(err) => failures.push((label, err)),
+            }
+        }
+        (successes, failures)
+    }
+
+    /// Every server's result.
+    pub fn iter(&self) -> impl Iterator<Item = (&L, &Result<(), E>)> {
+        self.results.iter().map(|(label, result)| (label, result))
+    }
+
+    /// How many servers there are results for.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether there are no results, because there were no servers.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+impl<L, E> FromIterator<(L, Result<(), E>)> for FleetResult<L, E> {
+    fn from_iter<I: IntoIterator<Item = (L, Result<(), E>)>>(iter: I) -> Self {
+        FleetResult {
+            results: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<L, E> IntoIterator for FleetResult<L, E> {
+    type Item = (L, Result<(), E>);
+    type IntoIter = std::vec::IntoIter<(L, Result<(), E>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<L: Display, E: Display> Display for FleetResult<L, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, (label, result)) in self.results.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            match result {
+                Ok(()) => write!(f, "{}: done", label)?,
+                Err(err) => write!(f, "{}: {}", label, err)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Forward events to any [`Sink`], such as a channel or a file writer.
+//!
+//! This module requires the `sink` feature.
+
+use crate::{ClientRead, Event};
+use futures_sink::Sink;
+use std::future::poll_fn;
+use std::io::ErrorKind;
+
+/// An error describing why forwarding events to a sink stopped.
+#[derive(Debug, thiserror::Error)]
+pub enum ForwardError<E> {
+    /// The sink returned an error.
+    #[error("sink error: {0}")]
+    Sink(E),
+
+    /// Receiving events from the RCON server failed.
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+impl ClientRead {
+    /// Send every event received to `sink`, until the connection closes or either fails.
+    ///
+    /// The next event isn't read until the sink is ready for it, so a slow sink slows down reading
+    /// instead of events building up in memory. Each event is flushed once it's sent. When the
+    /// server closes the connection, the sink is closed and this returns `Ok`. If reading fails
+    /// any other way, the sink is closed before the error is returned.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, Event};
+    /// use tokio::sync::mpsc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     let (tx, mut rx) = mpsc::channel(64);
+    ///     tokio::spawn(async move {
+    ///         while let Some(line) = rx.recv().await {
+    ///             println!("> {}", line);
+    ///         }
+    ///     });
+    ///
+    ///     let sink = futures_util::sink::unfold(tx, |tx, event: Event| async move {
+    ///         if let Event::ConsoleLog(line) = event {
+    ///             tx.send(line).await?;
+    ///         }
+    ///         Ok::<_, mpsc::error::SendError<String>>(tx)
+    ///     });
+    ///     read.forward_to(sink).await.unwrap();
+    /// }
+    /// ```
+    pub async fn forward_to<S>(&mut self, sink: S) -> Result<(), ForwardError<S::Error>>
+    where
+        S: Sink<Event>,
+    {
+        tokio::pin!(sink);
+        loop {
+            poll_fn(|cx| sink.as_mut().poll_ready(cx))
+                .await
+                .map_err(ForwardError::Sink)?;
+
+            let event = match self.receive_event().await {
+                Ok(event) => event,
+                Err(err) => {
+                    poll_fn(|cx| sink.as_mut().poll_close(cx))
+                        .await
+                        .map_err(ForwardError::Sink)?;
+                    return match err {
+                        crate::Error::Io(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(()),
+                        err => Err(err.into()),
+                    };
+                }
+            };
+
+            sink.as_mut()
+                .start_send(event)
+                .map_err(ForwardError::Sink)?;
+            poll_fn(|cx| sink.as_mut().poll_flush(cx))
+                .await
+                .map_err(ForwardError::Sink)?;
+        }
+    }
+}
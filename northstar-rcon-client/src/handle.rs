@@ -0,0 +1,387 @@
+use crate::ClientWrite;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+/// A request waiting to be sent by the writer task.
+enum Queued {
+    SetValue { var: String, val: String },
+    ExecCommand { cmd: String },
+    EnableConsoleLogs,
+}
+
+// A queued request, when it was queued, and where to send the result of sending it
+type Message = (Queued, Instant, oneshot::Sender<crate::Result<()>>);
+
+/// How fast a [`CommandHandle`] sends requests, to keep bursts from flooding the server's console.
+///
+/// Up to `burst` requests are sent straight away, and after that they're sent at the sustained
+/// rate. Time spent below the rate builds the burst back up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SendRate {
+    burst: u32,
+    per_second: f64,
+}
+
+impl SendRate {
+    /// Allow bursts of up to `burst` requests, and `per_second` requests a second after that.
+    ///
+    /// # Panics
+    /// Panics if `burst` is `0` or `per_second` isn't positive.
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        assert!(burst > 0, "burst must be at least 1");
+        assert!(per_second > 0., "per_second must be positive");
+        SendRate { burst, per_second }
+    }
+}
+
+/// Measurements of a [`CommandHandle`]'s queue, from [`CommandHandle::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Requests waiting to be sent.
+    pub queued: usize,
+
+    /// Requests sent, including ones that failed.
+    pub sent: u64,
+
+    /// How long the last request sent waited in the queue.
+    pub last_delay: Duration,
+
+    /// The longest any request has waited in the queue.
+    pub max_delay: Duration,
+}
+
+/// A cheap, cloneable handle for sending commands through one connection.
+///
+/// Requests are queued for a single task that owns the writer, and are sent in the order they
+/// were queued. The task stops once every handle has been dropped, which closes the write end of
+/// the connection. It also stops if sending fails, and any requests still queued then fail with
+/// [`NotConnected`](std::io::ErrorKind::NotConnected). Created with [`ClientWrite::into_handle`].
+///
+/// Unlike [`SharedClientWrite`](crate::SharedClientWrite), callers never wait for each other to
+/// get the writer, only for their own request to be sent. Handles created with
+/// [`ClientWrite::into_handle_with_rate`] also wait for the queue to be sent at its [`SendRate`].
+#[derive(Debug, Clone)]
+pub struct CommandHandle {
+    queue: mpsc::UnboundedSender<Message>,
+    stats: Arc<Mutex<QueueStats>>,
+}
+
+impl ClientWrite {
+    /// Move this writer onto a background task, and get a handle for queueing requests on it.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, write) = client.authenticate("password123").await.unwrap();
+    ///     let handle = write.into_handle();
+    ///
+    ///     for delay in [60, 30, 10] {
+    ///         let handle = handle.clone();
+    ///         tokio::spawn(async move {
+    ///             tokio::time::sleep(std::time::Duration::from_secs(60 - delay)).await;
+    ///             let cmd = format!("say Restarting in {} seconds", delay);
+    ///             handle.exec_command(cmd).await.unwrap();
+    ///         });
+    ///     }
+    ///
+    ///     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    ///     handle.exec_command("quit").await.unwrap();
+    /// }
+    /// ```
+    pub fn into_handle(self) -> CommandHandle {
+        self.spawn_handle(None)
+    }
+
+    /// Like [`into_handle`](ClientWrite::into_handle), but sending requests no faster than `rate`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{connect, SendRate};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, write) = client.authenticate("password123").await.unwrap();
+    ///
+    ///     // Send the first 5 straight away, then 2 a second
+    ///     let handle = write.into_handle_with_rate(SendRate::new(5, 2.));
+    ///     for n in 0..20 {
+    ///         handle.say(&format!("Message {}", n)).await.unwrap();
+    ///     }
+    ///
+    ///     println!("{:?}", handle.stats());
+    /// }
+    /// ```
+    pub fn into_handle_with_rate(self, rate: SendRate) -> CommandHandle {
+        self.spawn_handle(Some(rate))
+    }
+
+    fn spawn_handle(self, rate: Option<SendRate>) -> CommandHandle {
+        let (queue, requests) = mpsc::unbounded_channel();
+        let stats = Arc::new(Mutex::new(QueueStats::default()));
+        tokio::spawn(send_queued(self, requests, rate, stats.clone()));
+        CommandHandle { queue, stats }
+    }
+}
+
+impl CommandHandle {
+    /// Set the value of a ConVar if it exists, waiting until the request has been sent.
+    ///
+    /// See [`ClientWrite::set_value`].
+    pub async fn set_value(
+        &self,
+        var: impl Into<String>,
+        val: impl Into<String>,
+    ) -> crate::Result<()> {
+        self.send(Queued::SetValue {
+            var: var.into(),
+            val: val.into(),
+        })
+        .await
+    }
+
+    /// Execute a command remotely, waiting until the request has been sent.
+    ///
+    /// See [`ClientWrite::exec_command`].
+    pub async fn exec_command(&self, cmd: impl Into<String>) -> crate::Result<()> {
+        self.send(Queued::ExecCommand { cmd: cmd.into() }).await
+    }
+
+    /// Send a chat message to every player, waiting until the request has been sent.
+    ///
+    /// See [`ClientWrite::say`].
+    pub async fn say(&self, message: &str) -> crate::Result<()> {
+        self.exec_command(format!("say {}", crate::quote(message)))
+            .await
+    }
+
+    /// Enable console logs being sent to RCON clients, waiting until the request has been sent.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub async fn enable_console_logs(&self) -> crate::Result<()> {
+        self.send(Queued::EnableConsoleLogs).await
+    }
+
+    /// Measure the queue shared by this handle and its clones.
+    pub fn stats(&self) -> QueueStats {
+        *self.stats.lock().unwrap()
+    }
+
+    // Queue a request and wait for its result. If the writer task has stopped after an error, the
+    // request fails with `NotConnected`.
+    async fn send(&self, request: Queued) -> crate::Result<()> {
+        let not_connected = || std::io::Error::from(std::io::ErrorKind::NotConnected).into();
+
+        // Counted before queueing, so the writer task never sees it uncounted
+        self.stats.lock().unwrap().queued += 1;
+        let (result, receive_result) = oneshot::channel();
+        if self.queue.send((request, Instant::now(), result)).is_err() {
+            self.stats.lock().unwrap().queued -= 1;
+            return Err(not_connected());
+        }
+        receive_result
+            .await
+            .unwrap_or_else(|_| Err(not_connected()))
+    }
+}
+
+async fn send_queued(
+    mut write: ClientWrite,
+    mut requests: mpsc::UnboundedReceiver<Message>,
+    rate: Option<SendRate>,
+    stats: Arc<Mutex<QueueStats>>,
+) {
+    let mut bucket = rate.map(TokenBucket::new);
+
+    while let Some((request, queued_at, result)) = requests.recv().await {
+        if let Some(bucket) = &mut bucket {
+            bucket.take().await;
+        }
+
+        {
+            let mut stats = stats.lock().unwrap();
+            let delay = queued_at.elapsed();
+            stats.queued -= 1;
+            stats.sent += 1;
+            stats.last_delay = delay;
+            stats.max_delay = stats.max_delay.max(delay);
+        }
+
+        let sent = match &request {
+            Queued::SetValue { var, val } => write.set_value(var, val).await,
+            Queued::ExecCommand { cmd } => write.exec_command(cmd).await,
+            Queued::EnableConsoleLogs => write.enable_console_logs().await,
+        };
+
+        // Nothing more can be sent once the connection has failed
+        let failed = sent.is_err();
+        let _ = result.send(sent);
+        if failed {
+            break;
+        }
+    }
+
+    // Requests still queued fail with `NotConnected` once they're dropped, after they stop being
+    // counted
+    requests.close();
+    while let Ok(_message) = requests.try_recv() {
+        stats.lock().unwrap().queued -= 1;
+    }
+}
+
+// Requests that can be sent straight away, refilled at the sustained rate up to the burst size
+struct TokenBucket {
+    rate: SendRate,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: SendRate) -> Self {
+        TokenBucket {
+            rate,
+            tokens: rate.burst as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    // Wait until a request can be sent, and use up its token
+    async fn take(&mut self) {
+        let now = Instant::now();
+        let refilled = now.duration_since(self.updated).as_secs_f64() * self.rate.per_second;
+        self.tokens = (self.tokens + refilled).min(self.rate.burst as f64);
+        self.updated = now;
+
+        if self.tokens < 1. {
+            let wait = Duration::from_secs_f64((1. - self.tokens) / self.rate.per_second);
+            tokio::time::sleep(wait).await;
+            self.tokens = 1.;
+            self.updated = Instant::now();
+        }
+        self.tokens -= 1.;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestConnection, TestServer};
+
+    // Connect to a server, and get a handle for the writer
+    async fn connect(rate: Option<SendRate>) -> (CommandHandle, TestConnection) {
+        let server = TestServer::bind().await;
+        let (_, write, connection) = server.connect().await;
+        (write.spawn_handle(rate), connection)
+    }
+
+    // Send commands one after another, returning when each was sent
+    async fn send_each(handle: &CommandHandle, count: usize) -> Vec<Duration> {
+        let start = Instant::now();
+        let mut sent_at = Vec::new();
+        for n in 0..count {
+            handle.exec_command(format!("say {}", n)).await.unwrap();
+            sent_at.push(start.elapsed());
+        }
+        sent_at
+    }
+
+    fn secs(secs: &[f64]) -> Vec<Duration> {
+        secs.iter()
+            .map(|&secs| Duration::from_secs_f64(secs))
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_rate() {
+        let (handle, mut connection) = connect(Some(SendRate::new(3, 2.))).await;
+
+        // The burst is sent straight away, then the rest at 2 a second
+        let sent_at = send_each(&handle, 6).await;
+        assert_eq!(sent_at, secs(&[0., 0., 0., 0.5, 1., 1.5]));
+        for n in 0..6 {
+            assert_eq!(connection.receive_command().await, format!("say {}", n));
+        }
+
+        // Waiting builds the burst back up, but no further
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let sent_at = send_each(&handle, 4).await;
+        assert_eq!(sent_at, secs(&[0., 0., 0., 0.5]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_stats() {
+        let (handle, _connection) = connect(Some(SendRate::new(1, 1.))).await;
+        let tasks: Vec<_> = (0..4)
+            .map(|n| {
+                let handle = handle.clone();
+                tokio::spawn(async move { handle.exec_command(format!("say {}", n)).await })
+            })
+            .collect();
+
+        // The first is sent, while the rest wait for the rate
+        while handle.stats().sent == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            handle.stats(),
+            QueueStats {
+                queued: 3,
+                sent: 1,
+                last_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+            }
+        );
+
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(
+            handle.stats(),
+            QueueStats {
+                queued: 0,
+                sent: 4,
+                last_delay: Duration::from_secs(3),
+                max_delay: Duration::from_secs(3),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn failure_drains_queue() {
+        let server = TestServer::bind().await;
+        let (_, mut write, connection) = server.connect().await;
+
+        // Wait for the closed connection to make writes fail
+        drop(connection);
+        while write.exec_command("status").await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        let handle = write.into_handle();
+        let (first, second, third) = tokio::join!(
+            handle.exec_command("a"),
+            handle.exec_command("b"),
+            handle.exec_command("c"),
+        );
+        assert!(first.is_err());
+        for result in [second, third] {
+            assert!(matches!(
+                result,
+                Err(crate::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotConnected
+            ));
+        }
+        assert!(matches!(
+            handle.exec_command("d").await,
+            Err(crate::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotConnected
+        ));
+
+        let stats = handle.stats();
+        assert_eq!((stats.queued, stats.sent), (0, 1));
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// A console log line kept in a client's history.
+///
+/// See [`ClientRead::set_history_capacity`].
+///
+/// [`ClientRead::set_history_capacity`]: crate::ClientRead::set_history_capacity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// When the line was received.
+    pub received_at: Instant,
+
+    /// The log line.
+    pub line: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+
+        // Drop the oldest entries if the history is now too long
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+    }
+
+    pub fn push(&mut self, line: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            received_at: Instant::now(),
+            line: line.to_string(),
+        });
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn entries_since(&self, since: Instant) -> impl DoubleEndedIterator<Item = &HistoryEntry> {
+        // Entries are in order, so everything after the first match is also newer
+        let start = self
+            .entries
+            .partition_point(|entry| entry.received_at < since);
+        self.entries.range(start..)
+    }
+}
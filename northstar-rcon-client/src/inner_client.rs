@@ -1,202 +1,709 @@
-use protobuf::Message;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "protocol-trace")]
+use crate::protocol_trace::{FrameRecord, Tracer};
+use crate::CommandFilter;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use northstar_rcon_types::raw;
+use northstar_rcon_types::{
+    console_log_range, decode_response, encode_request, split_frame, DEFAULT_REQUEST_ID,
+};
+pub use northstar_rcon_types::{AuthError, AuthMatchers, Request, Response};
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 
 const READ_CHUNK_LEN: usize = 4096;
 
-#[derive(Debug, Clone, Copy)]
-pub enum Request<'a> {
-    Auth { pass: &'a str },
-    SetValue { var: &'a str, val: &'a str },
-    ExecCommand { cmd: &'a str },
-    EnableConsoleLogs,
-}
-
-#[derive(Debug)]
-pub enum Response {
-    Auth { res: Result<(), AuthError> },
-    ConsoleLog { msg: String },
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AuthError {
-    InvalidPassword,
-    Banned,
-}
-
 #[derive(Debug)]
 pub struct InnerClientWrite {
     write: OwnedWriteHalf,
+    next_request_id: Option<i32>,
+    last_request_id: i32,
+    buffer: WriteBuffer,
+    // Commands and ConVar changes are checked but not sent
+    dry_run: bool,
+    // Requests it denies fail without being sent
+    filter: Option<CommandFilter>,
+    #[cfg(feature = "protocol-trace")]
+    tracer: Option<Tracer>,
 }
 
 #[derive(Debug)]
 pub struct InnerClientRead {
     read: OwnedReadHalf,
-    buffer: Vec<u8>,
-    read_offset: usize,
+    // Received bytes that haven't been returned as a frame yet
+    buffer: BytesMut,
+    #[cfg(feature = "protocol-trace")]
+    tracer: Option<Tracer>,
 }
 
 impl InnerClientWrite {
     pub fn new(write: OwnedWriteHalf) -> Self {
-        InnerClientWrite { write }
+        InnerClientWrite {
+            write,
+            next_request_id: None,
+            last_request_id: DEFAULT_REQUEST_ID,
+            buffer: WriteBuffer::default(),
+            dry_run: false,
+            filter: None,
+            #[cfg(feature = "protocol-trace")]
+            tracer: None,
+        }
+    }
+
+    #[cfg(feature = "protocol-trace")]
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub fn set_auto_request_ids(&mut self, enabled: bool) {
+        self.next_request_id = match (enabled, self.next_request_id) {
+            (true, Some(next)) => Some(next),
+            (true, None) => Some(0),
+            (false, _) => None,
+        };
+    }
+
+    pub fn last_request_id(&self) -> i32 {
+        self.last_request_id
+    }
+
+    pub fn set_dry_run(&mut self, enabled: bool) {
+        self.dry_run = enabled;
+    }
+
+    pub fn set_filter(&mut self, filter: Option<CommandFilter>) {
+        self.filter = filter;
     }
 
     pub async fn send(&mut self, request: Request<'_>) -> crate::Result<()> {
-        let mut buf: Vec<u8> = Vec::new();
+        let request_id = self.next_request_id();
+        self.send_with_id(request, request_id).await
+    }
+
+    // Add a request to the buffer to be written by `poll_write_buffer`, keeping it even if nothing
+    // is written before the next send
+    pub fn start_send(&mut self, request: Request<'_>) -> crate::Result<()> {
+        let request_id = self.next_request_id();
+        if self.queue_with_id(request, request_id)? {
+            self.buffer.start();
+        }
+        Ok(())
+    }
+
+    fn next_request_id(&mut self) -> i32 {
+        match &mut self.next_request_id {
+            Some(next) => {
+                let request_id = *next;
+
+                // Wrap back to zero to avoid the negative default ID
+                *next = next.checked_add(1).unwrap_or(0);
+
+                request_id
+            }
+            None => DEFAULT_REQUEST_ID,
+        }
+    }
+
+    pub async fn send_with_id(
+        &mut self,
+        request: Request<'_>,
+        request_id: i32,
+    ) -> crate::Result<()> {
+        if self.queue_with_id(request, request_id)? {
+            self.write_buffer().await?;
+        }
+        Ok(())
+    }
+
+    // Add a request to the buffer, returning whether it needs writing
+    fn queue_with_id(&mut self, request: Request<'_>, request_id: i32) -> crate::Result<bool> {
+        if let Some(filter) = &self.filter {
+            filter.check(&request)?;
+        }
 
-        // Insert a placeholder for the buffer length
-        buf.extend_from_slice(&0u32.to_be_bytes());
+        let dry_run = self.dry_run
+            && matches!(
+                request,
+                Request::SetValue { .. } | Request::ExecCommand { .. }
+            );
 
-        // Encode data into the buffer
-        crate::protocol::Request::from(request)
-            .write_to(&mut protobuf::CodedOutputStream::new(&mut buf))?;
+        let mut proto_request = raw::Request::from(request);
+        proto_request.requestID = Some(request_id);
+        self.last_request_id = request_id;
 
-        // Set the buffer length to the actual value
-        let len_bytes = ((buf.len() - std::mem::size_of::<u32>()) as u32).to_be_bytes();
-        buf[..std::mem::size_of::<u32>()].copy_from_slice(&len_bytes);
+        if dry_run {
+            // Encode the request anyway, so it fails like it would have if it can't be sent
+            encode_request(&proto_request, &mut Vec::new())?;
+
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                id = request_id,
+                buf = ?proto_request.requestBuf,
+                val = ?proto_request.requestVal,
+                "dry run, not sent"
+            );
+
+            return Ok(false);
+        }
 
-        self.write.write_all(&buf).await?;
+        self.queue_proto(proto_request)
+    }
+
+    #[cfg(feature = "raw-protocol")]
+    pub async fn send_proto(&mut self, request: raw::Request) -> crate::Result<()> {
+        if self.queue_proto(request)? {
+            self.write_buffer().await?;
+        }
         Ok(())
     }
+
+    // Add a raw request to the buffer, returning whether it needs writing
+    fn queue_proto(&mut self, request: raw::Request) -> crate::Result<bool> {
+        let _len = self.buffer.push(&request)?;
+
+        #[cfg(feature = "tracing")]
+        trace_request(&request, _len);
+
+        #[cfg(feature = "protocol-trace")]
+        if let Some(tracer) = &self.tracer {
+            tracer.record(&FrameRecord::sent(&request, _len));
+        }
+
+        Ok(!self.buffer.corked)
+    }
+
+    pub fn cork(&mut self) {
+        self.buffer.cork();
+    }
+
+    pub async fn uncork(&mut self) -> crate::Result<()> {
+        self.buffer.corked = false;
+        self.write_buffer().await
+    }
+
+    // Write the rest of the buffer. Unlike `write_all`, progress is kept if this is cancelled.
+    async fn write_buffer(&mut self) -> crate::Result<()> {
+        std::future::poll_fn(|cx| self.poll_write_buffer(cx)).await
+    }
+
+    pub fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        ready!(self.poll_write_buffer(cx))?;
+        Pin::new(&mut self.write).poll_flush(cx).map_err(Into::into)
+    }
+
+    pub fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        ready!(self.poll_flush(cx))?;
+        Pin::new(&mut self.write)
+            .poll_shutdown(cx)
+            .map_err(Into::into)
+    }
+
+    pub fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        self.buffer.poll_write(cx, &mut self.write)
+    }
+}
+
+// Encoded requests waiting to be written, which keeps track of where each frame ends so the
+// server is only ever sent whole frames, even if a write is cancelled or fails.
+#[derive(Debug, Default)]
+struct WriteBuffer {
+    // Reused for encoding every request, to avoid allocating each time
+    buf: Vec<u8>,
+    // Where each frame in `buf` ends
+    frame_ends: Vec<usize>,
+    // How much of the buffer has been written. If a send is cancelled part way through a frame,
+    // the rest of it is written before the next request.
+    written: usize,
+    // Set when writing failed part way through a frame, which leaves the stream unusable
+    corrupted: bool,
+    // While corked, requests are added to the buffer without being written
+    corked: bool,
+    // Set when requests were added with `start_send`, which are written even if nothing has been
+    // yet
+    started: bool,
+}
+
+impl WriteBuffer {
+    // Encode a request onto the end of the buffer, returning the length of its frame
+    fn push(&mut self, request: &raw::Request) -> crate::Result<usize> {
+        if self.corrupted {
+            return Err(crate::Error::CorruptedStream);
+        }
+
+        if !self.corked {
+            self.discard_written();
+        }
+
+        let start = self.buf.len();
+        if let Err(err) = encode_request(request, &mut self.buf) {
+            self.buf.truncate(start);
+            return Err(err.into());
+        }
+        self.frame_ends.push(self.buf.len());
+        Ok(self.buf.len() - start)
+    }
+
+    // Keep the frames in the buffer even if nothing is written before the next push
+    fn start(&mut self) {
+        self.started = true;
+    }
+
+    fn cork(&mut self) {
+        if !self.corked {
+            self.discard_written();
+            self.corked = true;
+        }
+    }
+
+    // Remove frames that have been written from the buffer. A frame that was only partly written
+    // is kept so the rest of it is written next. Frames that a cancelled or failed write hadn't
+    // started on are dropped, unless they were added with `start_send`.
+    fn discard_written(&mut self) {
+        let done = self
+            .frame_ends
+            .iter()
+            .take_while(|&&end| end <= self.written)
+            .count();
+        if done == self.frame_ends.len() {
+            self.buf.clear();
+            self.frame_ends.clear();
+            self.written = 0;
+            self.started = false;
+            return;
+        }
+
+        if done > 0 {
+            let done_len = self.frame_ends[done - 1];
+            self.buf.drain(..done_len);
+            self.frame_ends.drain(..done);
+            for end in &mut self.frame_ends {
+                *end -= done_len;
+            }
+            self.written -= done_len;
+        }
+
+        if !self.started {
+            let partial = usize::from(self.written > 0);
+            self.frame_ends.truncate(partial);
+            self.buf
+                .truncate(self.frame_ends.last().copied().unwrap_or(0));
+        }
+    }
+
+    // Whether everything written so far ends on a frame boundary
+    fn at_frame_boundary(&self) -> bool {
+        self.written == 0 || self.frame_ends.contains(&self.written)
+    }
+
+    fn poll_write<W>(&mut self, cx: &mut Context<'_>, write: &mut W) -> Poll<crate::Result<()>>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if self.corrupted {
+            return Poll::Ready(Err(crate::Error::CorruptedStream));
+        }
+
+        while self.written < self.buf.len() {
+            let write = Pin::new(&mut *write);
+            let result = match ready!(write.poll_write(cx, &self.buf[self.written..])) {
+                Ok(0) => Err(std::io::Error::from(std::io::ErrorKind::WriteZero)),
+                result => result,
+            };
+
+            match result {
+                Ok(len) => self.written += len,
+                Err(err) => {
+                    // The server has only been sent whole frames, so the stream is still usable.
+                    // The frames that weren't written stay buffered, and are dropped by the next
+                    // push unless they were started.
+                    if !self.at_frame_boundary() {
+                        self.corrupted = true;
+                    }
+                    return Poll::Ready(Err(err.into()));
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
 }
 
 impl InnerClientRead {
     pub fn new(read: OwnedReadHalf) -> Self {
         InnerClientRead {
             read,
-            buffer: Vec::new(),
-            read_offset: 0,
+            buffer: BytesMut::new(),
+            #[cfg(feature = "protocol-trace")]
+            tracer: None,
         }
     }
 
-    pub async fn receive(&mut self) -> crate::Result<Response> {
-        // Repeatedly fetch data from the remote until we have a response
+    #[cfg(feature = "protocol-trace")]
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// Like [`receive`](InnerClientRead::receive), reading authentication responses with
+    /// `matchers`.
+    pub async fn receive_with(&mut self, matchers: &AuthMatchers) -> crate::Result<Response> {
         loop {
-            // Pull any queued responses from the receive buffer
-            while let Some((response_buffer, remaining_buffer)) =
-                get_message_from_slice(&self.buffer[self.read_offset..])
-            {
-                // Consume the bytes
-                self.read_offset = self.buffer.len() - remaining_buffer.len();
-
-                // Parse and return the response
-                let proto_response = crate::protocol::Response::parse_from(
-                    &mut protobuf::CodedInputStream::from_bytes(response_buffer),
-                )?;
-                match Response::try_from(proto_response) {
-                    Ok(res) => return Ok(res),
-                    Err(()) => continue,
-                };
+            if let Some(res) = Response::from_raw(self.receive_proto().await?, matchers) {
+                return Ok(res);
             }
+        }
+    }
+
+    // Ready once there's a whole frame buffered or anything to read from the connection,
+    // including it having closed
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<()>> {
+        if split_frame(&self.buffer).is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut byte = [0];
+        let mut peek_buf = ReadBuf::new(&mut byte);
+        ready!(self.read.poll_peek(cx, &mut peek_buf))?;
+        Poll::Ready(Ok(()))
+    }
 
-            // If all of the buffer has been consumed, it can be completely re-used
-            if self.read_offset == self.buffer.len() {
-                self.buffer.clear();
-                self.read_offset = 0;
+    /// Receive the next response, skipping any that aren't understood. If `unknown` is set, those
+    /// are instead returned as `Err`.
+    pub fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        unknown: bool,
+    ) -> Poll<crate::Result<Result<Response, raw::Response>>> {
+        loop {
+            let proto_response = ready!(self.poll_receive_proto(cx))?;
+            if unknown {
+                let res = Response::try_from(proto_response.clone()).map_err(|()| proto_response);
+                return Poll::Ready(Ok(res));
+            }
+            if let Ok(res) = Response::try_from(proto_response) {
+                return Poll::Ready(Ok(Ok(res)));
             }
+        }
+    }
 
-            // Add some space to write into
-            let write_start = self.buffer.len();
-            self.buffer.resize(write_start + READ_CHUNK_LEN, 0);
+    pub async fn receive_proto(&mut self) -> crate::Result<raw::Response> {
+        std::future::poll_fn(|cx| self.poll_receive_proto(cx)).await
+    }
 
-            let write_len = self.read.read(&mut self.buffer[write_start..]).await?;
+    pub fn poll_receive_proto(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<crate::Result<raw::Response>> {
+        let frame = ready!(self.poll_receive_frame(cx))?;
+        let proto_response = decode_response(&frame)?;
 
-            if write_len == 0 {
-                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        #[cfg(feature = "tracing")]
+        trace_response(&proto_response, frame.len());
+
+        #[cfg(feature = "protocol-trace")]
+        if let Some(tracer) = &self.tracer {
+            tracer.record(&FrameRecord::received(&proto_response, frame.len()));
+        }
+
+        Poll::Ready(Ok(proto_response))
+    }
+
+    /// Receive the next console log line without copying it out of the receive buffer, skipping
+    /// any other responses.
+    pub async fn receive_console_log_bytes(&mut self) -> crate::Result<Bytes> {
+        loop {
+            let frame = self.receive_frame().await?;
+
+            // Frames are only decoded here when they need to be recorded
+            #[cfg(feature = "protocol-trace")]
+            if let Some(tracer) = &self.tracer {
+                if let Ok(proto_response) = decode_response(&frame) {
+                    tracer.record(&FrameRecord::received(&proto_response, frame.len()));
+                }
             }
 
-            // Shrink the buffer again so it only contains written data
-            self.buffer.truncate(write_start + write_len);
+            if let Some(range) = console_log_range(&frame) {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(len = frame.len(), "received console log frame");
+
+                return Ok(frame.slice(range));
+            }
         }
     }
-}
 
-impl From<Request<'_>> for crate::protocol::Request {
-    fn from(request: Request<'_>) -> Self {
-        let (request_type, request_buf, request_val) = match request {
-            Request::Auth { pass, .. } => (
-                crate::protocol::Request_t::SERVERDATA_REQUEST_AUTH,
-                Some(pass.to_string()),
-                None,
-            ),
-            Request::SetValue { var, val, .. } => (
-                crate::protocol::Request_t::SERVERDATA_REQUEST_SETVALUE,
-                Some(var.to_string()),
-                Some(val.to_string()),
-            ),
-            Request::ExecCommand { cmd, .. } => (
-                crate::protocol::Request_t::SERVERDATA_REQUEST_EXECCOMMAND,
-                Some(cmd.to_string()),
-                None,
-            ),
-            Request::EnableConsoleLogs => (
-                crate::protocol::Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG,
-                None,
-                None,
-            ),
-        };
+    async fn receive_frame(&mut self) -> crate::Result<Bytes> {
+        std::future::poll_fn(|cx| self.poll_receive_frame(cx)).await
+    }
+
+    fn poll_receive_frame(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<Bytes>> {
+        // Repeatedly fetch data from the remote until we have a whole frame
+        loop {
+            // Split a queued frame off the receive buffer. The frame shares the buffer's memory,
+            // which is reused once every frame split off it has been dropped.
+            if let Some((frame, _)) = split_frame(&self.buffer) {
+                let frame_len = frame.len();
+                self.buffer.advance(std::mem::size_of::<u32>());
+                return Poll::Ready(Ok(self.buffer.split_to(frame_len).freeze()));
+            }
 
-        crate::protocol::Request {
-            requestID: Some(-1),
-            requestType: Some(protobuf::EnumOrUnknown::new(request_type)),
-            requestBuf: request_buf,
-            requestVal: request_val,
-            special_fields: protobuf::SpecialFields::default(),
+            // Make sure there is some space to write into. The spare capacity is read into
+            // without being zeroed first, and the length only grows once a read finishes, so if
+            // this is cancelled while reading the buffer stays valid.
+            self.buffer.reserve(READ_CHUNK_LEN);
+
+            let spare = self.buffer.chunk_mut();
+
+            // SAFETY: `UninitSlice` is a slice of possibly uninitialized bytes
+            let spare = unsafe {
+                std::slice::from_raw_parts_mut(
+                    spare.as_mut_ptr().cast::<MaybeUninit<u8>>(),
+                    spare.len(),
+                )
+            };
+            let mut read_buf = ReadBuf::uninit(spare);
+            ready!(Pin::new(&mut self.read).poll_read(cx, &mut read_buf))?;
+            let write_len = read_buf.filled().len();
+
+            if write_len == 0 {
+                let err = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+                return Poll::Ready(Err(err.into()));
+            }
+
+            // SAFETY: `poll_read` initialized this many bytes of the spare capacity
+            unsafe { self.buffer.advance_mut(write_len) };
         }
     }
 }
 
-impl TryFrom<crate::protocol::Response> for Response {
-    type Error = ();
+#[cfg(feature = "tracing")]
+fn trace_request(request: &raw::Request, len: usize) {
+    let request_type = request.requestType.map(|t| t.enum_value());
 
-    fn try_from(value: crate::protocol::Response) -> Result<Self, Self::Error> {
-        let proto_response_type = value.responseType.ok_or(())?.enum_value().map_err(|_| ())?;
+    // Never log passwords
+    let buf = match request_type {
+        Some(Ok(raw::Request_t::SERVERDATA_REQUEST_AUTH)) => Some("<redacted>"),
+        _ => request.requestBuf.as_deref(),
+    };
 
-        match proto_response_type {
-            crate::protocol::Response_t::SERVERDATA_RESPONSE_AUTH => {
-                let message: String = value.responseBuf.ok_or(())?;
-                let res = if message.contains("Admin password incorrect") {
-                    Err(AuthError::InvalidPassword)
-                } else if message.contains("Go away") {
-                    Err(AuthError::Banned)
-                } else {
-                    Ok(())
-                };
+    tracing::trace!(
+        len,
+        id = ?request.requestID,
+        kind = ?request_type,
+        buf = ?buf,
+        val = ?request.requestVal,
+        "sent frame"
+    );
+}
 
-                Ok(Response::Auth { res })
-            }
-            crate::protocol::Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG => {
-                Ok(Response::ConsoleLog {
-                    msg: value.responseBuf.ok_or(())?,
-                })
-            }
+#[cfg(feature = "tracing")]
+fn trace_response(response: &raw::Response, len: usize) {
+    tracing::trace!(
+        len,
+        id = ?response.responseID,
+        kind = ?response.responseType.map(|t| t.enum_value()),
+        buf = ?response.responseBuf,
+        val = ?response.responseVal,
+        "received frame"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::io;
+    use std::task::Waker;
 
-            crate::protocol::Response_t::SERVERDATA_RESPONSE_VALUE
-            | crate::protocol::Response_t::SERVERDATA_RESPONSE_UPDATE
-            | crate::protocol::Response_t::SERVERDATA_RESPONSE_STRING
-            | crate::protocol::Response_t::SERVERDATA_RESPONSE_REMOTEBUG => {
-                // Unknown/unused?
-                Err(())
+    enum Step {
+        // Accept up to this many bytes
+        Write(usize),
+        Pending,
+        Fail,
+    }
+
+    // Writes according to a script of steps, accepting anything once it runs out
+    #[derive(Default)]
+    struct MockWrite {
+        steps: VecDeque<Step>,
+        written: Vec<u8>,
+    }
+
+    impl MockWrite {
+        fn new(steps: impl IntoIterator<Item = Step>) -> Self {
+            MockWrite {
+                steps: steps.into_iter().collect(),
+                written: Vec::new(),
             }
         }
     }
-}
 
-// Expects a slice starting with a 32-bit length in big endian order.
-// Returns a slice containing that number of bytes after the length, and a slice containing
-// everything after the length.
-// Returns none if not enough data is provided.
-fn get_message_from_slice(slice: &[u8]) -> Option<(&[u8], &[u8])> {
-    if slice.len() < 4 {
-        return None;
+    impl AsyncWrite for MockWrite {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let len = match self.steps.pop_front() {
+                Some(Step::Write(len)) => len.min(buf.len()),
+                Some(Step::Pending) => return Poll::Pending,
+                Some(Step::Fail) => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+                None => buf.len(),
+            };
+            self.written.extend_from_slice(&buf[..len]);
+            Poll::Ready(Ok(len))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn request(cmd: &str) -> raw::Request {
+        raw::Request::from(Request::ExecCommand { cmd })
+    }
+
+    fn frame(cmd: &str) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_request(&request(cmd), &mut buf).unwrap();
+        buf
+    }
+
+    fn poll(buffer: &mut WriteBuffer, write: &mut MockWrite) -> Poll<crate::Result<()>> {
+        buffer.poll_write(&mut Context::from_waker(Waker::noop()), write)
+    }
+
+    #[test]
+    fn pending_keeps_progress() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::new([Step::Write(3), Step::Pending]);
+
+        buffer.push(&request("status")).unwrap();
+        assert!(poll(&mut buffer, &mut write).is_pending());
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        assert_eq!(write.written, frame("status"));
+    }
+
+    #[test]
+    fn cancelled_frame_is_finished_first() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::new([Step::Write(3), Step::Pending]);
+
+        // Stop polling part way through the frame, like a dropped future
+        buffer.push(&request("status")).unwrap();
+        assert!(poll(&mut buffer, &mut write).is_pending());
+
+        buffer.push(&request("maxplayers")).unwrap();
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        assert_eq!(
+            write.written,
+            [frame("status"), frame("maxplayers")].concat()
+        );
+    }
+
+    #[test]
+    fn cancelled_before_writing_is_dropped() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::new([Step::Pending]);
+
+        buffer.push(&request("status")).unwrap();
+        assert!(poll(&mut buffer, &mut write).is_pending());
+
+        buffer.push(&request("maxplayers")).unwrap();
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        assert_eq!(write.written, frame("maxplayers"));
+    }
+
+    #[test]
+    fn started_frames_are_kept() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::default();
+
+        buffer.push(&request("status")).unwrap();
+        buffer.start();
+
+        buffer.push(&request("maxplayers")).unwrap();
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        assert_eq!(
+            write.written,
+            [frame("status"), frame("maxplayers")].concat()
+        );
+    }
+
+    #[test]
+    fn failing_between_corked_frames_keeps_the_rest() {
+        let mut buffer = WriteBuffer::default();
+        let first_len = frame("status").len();
+        let mut write = MockWrite::new([Step::Write(first_len), Step::Fail]);
+
+        buffer.cork();
+        buffer.push(&request("status")).unwrap();
+        buffer.push(&request("maxplayers")).unwrap();
+        buffer.push(&request("map mp_glitch")).unwrap();
+
+        buffer.corked = false;
+        assert!(matches!(
+            poll(&mut buffer, &mut write),
+            Poll::Ready(Err(crate::Error::Io(_)))
+        ));
+        assert!(!buffer.corrupted);
+
+        // Retrying writes the frames that weren't, without repeating the first
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        let expected = [frame("status"), frame("maxplayers"), frame("map mp_glitch")];
+        assert_eq!(write.written, expected.concat());
     }
 
-    let (len_bytes, remaining_bytes) = slice.split_at(std::mem::size_of::<u32>());
+    #[test]
+    fn failing_between_frames_drops_unwritten_on_next_push() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::new([Step::Fail]);
 
-    let len = u32::from_be_bytes(len_bytes.try_into().unwrap());
-    if remaining_bytes.len() < std::mem::size_of::<u32>() {
-        return None;
+        buffer.push(&request("status")).unwrap();
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Err(_))));
+        assert!(!buffer.corrupted);
+
+        buffer.push(&request("maxplayers")).unwrap();
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Ok(()))));
+        assert_eq!(write.written, frame("maxplayers"));
     }
 
-    Some(remaining_bytes.split_at(len as usize))
+    #[test]
+    fn failing_mid_frame_corrupts() {
+        let mut buffer = WriteBuffer::default();
+        let first_len = frame("status").len();
+        let mut write = MockWrite::new([Step::Write(first_len + 2), Step::Fail]);
+
+        buffer.cork();
+        buffer.push(&request("status")).unwrap();
+        buffer.push(&request("maxplayers")).unwrap();
+
+        buffer.corked = false;
+        assert!(matches!(poll(&mut buffer, &mut write), Poll::Ready(Err(_))));
+        assert!(buffer.corrupted);
+        assert!(matches!(
+            poll(&mut buffer, &mut write),
+            Poll::Ready(Err(crate::Error::CorruptedStream))
+        ));
+        assert!(matches!(
+            buffer.push(&request("status")),
+            Err(crate::Error::CorruptedStream)
+        ));
+    }
+
+    #[test]
+    fn write_zero_is_an_error() {
+        let mut buffer = WriteBuffer::default();
+        let mut write = MockWrite::new([Step::Write(0)]);
+
+        buffer.push(&request("status")).unwrap();
+        let Poll::Ready(Err(crate::Error::Io(err))) = poll(&mut buffer, &mut write) else {
+            panic!("expected an IO error");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert!(!buffer.corrupted);
+    }
 }
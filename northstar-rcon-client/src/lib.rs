@@ -29,12 +29,70 @@
 //! }
 //! ```
 //!
+//! # Polling
+//! For use in hand-written futures, custom schedulers or GUI event loops, both halves also have
+//! `poll_*` methods in the style of Tokio's I/O traits, which register the task in the
+//! [`Context`](std::task::Context) to be woken instead of waiting:
+//! - [`ClientRead::poll_recv_ready`] and [`ClientRead::poll_recv`] to wait for and read events.
+//! - [`ClientWrite::start_send`] to queue a request, then [`ClientWrite::poll_send`] or
+//!   [`ClientWrite::poll_flush`] to write it, and [`ClientWrite::poll_close`] to shut down the
+//!   write half.
+//!
+//! # Features
+//! - `discord`: relay console logs to a Discord webhook with `discord::DiscordRelay`.
+//! - `file-sink`: write console logs to rotating files with `file_sink::FileSink`.
+//! - `protocol-trace`: pass a record of every frame sent and received, with its decoded fields,
+//!   to a callback with `NotAuthenticatedClient::protocol_trace`. See the `protocol_trace` module.
+//! - `raw-protocol`: send and receive the raw protobuf messages in the `raw` module.
+//! - `regen-protos`: generate the protocol types from the protobuf definitions when building,
+//!   instead of using the generated code that's checked in.
+//! - `sink`: forward events to any `futures::Sink` with `ClientRead::forward_to`. See the
+//!   `forward` module.
+//! - `syslog`: forward console logs to a syslog daemon with `syslog::SyslogSink`.
+//! - `tracing`: emit [`tracing`](https://docs.rs/tracing) events when connecting and
+//!   authenticating, and for every frame sent or received at the `TRACE` level. Passwords are
+//!   never included.
+//! - `webhook`: POST to a URL when log lines match a pattern with `webhook::WebhookNotifier`.
+//!
 //! [Northstar mod]: https://northstar.tf/
 //! [RCON PR]: https://github.com/R2Northstar/NorthstarLauncher/pull/100
 
+mod audit;
+mod autoscale;
+mod buffered;
 mod client;
+mod command;
+mod command_filter;
+mod convar;
+mod dedupe;
+#[cfg(feature = "discord")]
+pub mod discord;
+mod echo;
+#[cfg(feature = "file-sink")]
+pub mod file_sink;
+mod fleet;
+#[cfg(feature = "sink")]
+pub mod forward;
+mod handle;
+mod history;
 mod inner_client;
-mod protocol;
+mod manager;
+mod maps;
+mod population;
+#[cfg(feature = "protocol-trace")]
+pub mod protocol_trace;
+#[cfg(feature = "raw-protocol")]
+pub mod raw;
+mod reconnect;
+mod scoped;
+mod shared;
+mod status;
+#[cfg(feature = "syslog")]
+pub mod syslog;
+#[cfg(test)]
+mod test_server;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 /// Error type for RCON operations.
 #[derive(Debug, thiserror::Error)]
@@ -44,12 +102,54 @@ pub enum Error {
 
     #[error(transparent)]
     Parse(#[from] protobuf::Error),
+
+    /// The console log buffer was full, with [`OverflowPolicy::Error`].
+    #[error("console log buffer is full")]
+    Overflow,
+
+    /// An earlier request failed part way through being sent, so the server can't read anything
+    /// more sent on this connection. Reconnect to keep sending.
+    #[error("connection was left with a partly sent request")]
+    CorruptedStream,
+
+    /// A [`ScopedClient`] or [`CommandFilter`] didn't allow sending a command or setting a ConVar,
+    /// with this name.
+    #[error("{0} isn't allowed")]
+    PermissionDenied(String),
+
+    /// The server sent a response that wasn't expected, in
+    /// [strict mode](ClientRead::set_strict).
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(String),
+
+    /// A command built from arguments, like with [`ClientWrite::exec_command_args`], would run
+    /// more than one command.
+    #[error(transparent)]
+    InvalidRequest(#[from] InvalidRequest),
 }
 
 /// [`Result`] alias for [`Error`].
 pub type Result<T> = std::result::Result<T, Error>;
 
+pub use self::audit::*;
+pub use self::autoscale::*;
+pub use self::buffered::*;
 pub use self::client::*;
+pub use self::command::{quote, CommandBuilder, InvalidRequest, SetValueBuilder};
+pub use self::command_filter::CommandFilter;
+pub use self::convar::SetValueError;
+pub use self::echo::CommandEcho;
+pub use self::fleet::FleetResult;
+pub use self::handle::*;
+pub use self::history::HistoryEntry;
+pub use self::manager::*;
+pub use self::maps::*;
+pub use self::population::*;
+pub use self::reconnect::*;
+pub use self::scoped::ScopedClient;
+pub use self::shared::*;
+pub use self::status::{strip_tags, ServerInfo};
+pub use northstar_rcon_types::{AuthMatchers, AuthOutcome, LogLevel, Request};
 use tokio::net::ToSocketAddrs;
 
 /// Asynchronously connect to an RCON server.
@@ -0,0 +1,804 @@
+use crate::{
+    ClientRead, ClientWrite, CrossingDirection, FleetResult, PopulationMonitor, ReconnectError,
+    ReconnectingClient,
+};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// The connection status of a server owned by a [`ServerManager`].
+#[derive(Debug, Clone)]
+pub enum ServerStatus {
+    /// The server is being connected to for the first time.
+    Connecting,
+
+    /// The server is connected and authenticated.
+    Connected,
+
+    /// The connection was lost and the server is being reconnected to.
+    Reconnecting {
+        /// The error that caused the connection to be lost.
+        last_error: Arc<crate::Error>,
+    },
+
+    /// Reconnecting gave up, and no more attempts will be made.
+    Failed(Arc<ReconnectError>),
+}
+
+/// Something that happened on a server owned by a [`ServerManager`].
+#[derive(Debug, Clone)]
+pub enum ServerEvent {
+    /// The server was connected and authenticated.
+    Connected,
+
+    /// The server was restarted while disconnected, so anything set since it started, like
+    /// `sv_rcon_sendlogs`, has been reset. Sent after [`Connected`](ServerEvent::Connected).
+    ///
+    /// A restart is detected when the old connection was lost and the server refused a
+    /// connection before it could be reconnected to, meaning nothing was listening for a while.
+    /// If console logs were enabled with [`ServerManager::enable_console_logs`], they're enabled
+    /// again after every reconnect.
+    Restarted,
+
+    /// Console logs had stopped being sent, probably because someone set `sv_rcon_sendlogs` to
+    /// `0`, and were enabled again. Only sent with [`ServerManager::log_watchdog`].
+    ConsoleLogsReenabled,
+
+    /// The server's player count moved past one of the thresholds of the
+    /// [`ServerManager::population_monitor`].
+    PopulationCrossed {
+        threshold: u32,
+        direction: CrossingDirection,
+    },
+
+    /// The server sent a console log line.
+    ConsoleLog(String),
+
+    /// The connection was lost. The manager will try to reconnect.
+    Disconnected(Arc<crate::Error>),
+
+    /// Reconnecting gave up, and no more attempts will be made.
+    Failed(Arc<ReconnectError>),
+}
+
+/// An error describing why a [`ServerManager`] operation failed.
+#[derive(Debug, thiserror::Error)]
+pub enum ManagerError {
+    /// No server with the label exists.
+    #[error("unknown server")]
+    UnknownServer,
+
+    /// The server has failed and will not reconnect.
+    #[error("server has stopped")]
+    Stopped,
+
+    /// The request wasn't sent in time, such as because the server was reconnecting. It may
+    /// still be sent later.
+    #[error("timed out")]
+    TimedOut,
+
+    /// The connection was lost while sending the request, and it won't be sent.
+    #[error("connection lost")]
+    Disconnected,
+}
+
+/// Something received by a [`ServerSubscriber`].
+#[derive(Debug, Clone)]
+pub enum SubscriberEvent<L> {
+    /// An event from a server, tagged with its label.
+    Event(L, ServerEvent),
+
+    /// The subscriber fell behind, and this many of the oldest events were dropped to catch up.
+    Lagged(u64),
+}
+
+/// How many events a [`ServerSubscriber`] can fall behind by before events are dropped, unless
+/// [`ServerManager::subscriber_capacity`] is used.
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+// How long the log watchdog waits for `sv_rcon_sendlogs` to be printed before deciding console
+// logs are disabled
+const LOG_WATCHDOG_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+enum QueuedRequest {
+    ExecCommand(String),
+    SetValue(String, String),
+    EnableConsoleLogs,
+}
+
+// A request for a server's task, with where to say it's been sent if anything is waiting for it
+type Message = (QueuedRequest, Option<oneshot::Sender<()>>);
+
+#[derive(Debug)]
+struct ServerHandle {
+    status: Arc<Mutex<ServerStatus>>,
+    requests: mpsc::UnboundedSender<Message>,
+    task: JoinHandle<()>,
+}
+
+/// Owns connections to many servers, each identified by a label.
+///
+/// Every server is connected with a [`ReconnectingClient`] on its own task, so servers are
+/// reconnected to independently when their connection drops. Events from all servers are merged
+/// into a single stream, tagged with the label of the server they came from.
+///
+/// Commands sent while a server is reconnecting are queued and sent once it's connected again.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::{ReconnectingClient, ServerEvent, ServerManager};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut manager = ServerManager::new();
+///
+///     for (label, addr) in [("eu-1", "10.0.0.1:37015"), ("us-1", "10.0.1.1:37015")] {
+///         let client = ReconnectingClient::new(addr, "password123").enable_console_logs(true);
+///         manager.add(label, client);
+///     }
+///
+///     manager.exec_command(&"eu-1", "map mp_glitch").unwrap();
+///
+///     loop {
+///         let (label, event) = manager.next_event().await;
+///         if let ServerEvent::ConsoleLog(line) = event {
+///             println!("[{}] {}", label, line);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ServerManager<L> {
+    servers: HashMap<L, ServerHandle>,
+    // Labels in the order the servers were added
+    order: Vec<L>,
+    // Subscribed when the manager is created, so `next_event` gets events from the start
+    events: ServerSubscriber<L>,
+    subscribers: broadcast::Sender<(L, ServerEvent)>,
+    log_watchdog: Option<Duration>,
+    population_monitor: Option<PopulationMonitor>,
+}
+
+/// Receives the events from every server owned by a [`ServerManager`], independently of any
+/// other subscribers.
+///
+/// Each subscriber has a bounded buffer. A subscriber that falls too far behind misses the oldest
+/// events instead of the buffer growing, and is told how many with
+/// [`SubscriberEvent::Lagged`].
+///
+/// See [`ServerManager::subscribe`].
+#[derive(Debug)]
+pub struct ServerSubscriber<L> {
+    events: broadcast::Receiver<(L, ServerEvent)>,
+    lagged: u64,
+}
+
+impl<L: Clone + Eq + Hash + Send + Sync + 'static> ServerManager<L> {
+    /// Create a manager with no servers.
+    pub fn new() -> Self {
+        let (subscribers, events) = broadcast::channel(DEFAULT_SUBSCRIBER_CAPACITY);
+        ServerManager {
+            servers: HashMap::new(),
+            order: Vec::new(),
+            events: ServerSubscriber { events, lagged: 0 },
+            subscribers,
+            log_watchdog: None,
+            population_monitor: None,
+        }
+    }
+
+    /// Set how many events each [`ServerSubscriber`], and [`next_event`], can fall behind by
+    /// before events are dropped. The default is [`DEFAULT_SUBSCRIBER_CAPACITY`].
+    ///
+    /// This has to be set before any servers are added or subscribers are created.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is `0`.
+    ///
+    /// [`next_event`]: ServerManager::next_event
+    pub fn subscriber_capacity(mut self, capacity: usize) -> Self {
+        let (subscribers, events) = broadcast::channel(capacity);
+        self.subscribers = subscribers;
+        self.events = ServerSubscriber { events, lagged: 0 };
+        self
+    }
+
+    /// Watch for console logs stopping on servers that should be sending them, and enable them
+    /// again.
+    ///
+    /// When a server has sent no lines for `silence`, `sv_rcon_sendlogs` is run on it. If its
+    /// value isn't printed within a few seconds, console logs are enabled again and a
+    /// [`ServerEvent::ConsoleLogsReenabled`] event is sent. This only watches servers whose
+    /// [`ReconnectingClient`] enables console logs, or that had them enabled with
+    /// [`enable_console_logs`](ServerManager::enable_console_logs).
+    ///
+    /// This has to be set before servers are added.
+    pub fn log_watchdog(mut self, silence: Duration) -> Self {
+        self.log_watchdog = Some(silence);
+        self
+    }
+
+    /// Run `status` on every server at the monitor's interval, and send a
+    /// [`ServerEvent::PopulationCrossed`] event when a server's player count crosses one of its
+    /// thresholds.
+    ///
+    /// Each server gets its own copy of the monitor. Player counts are read from the console
+    /// logs, so these need to be enabled.
+    ///
+    /// This has to be set before servers are added.
+    ///
+    /// # Panics
+    /// Panics if the monitor's [`interval`](PopulationMonitor::interval) is zero.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{
+    ///     CrossingDirection, PopulationMonitor, ReconnectingClient, ServerEvent, ServerManager,
+    /// };
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let monitor = PopulationMonitor::new(Duration::from_secs(60)).threshold(1);
+    ///     let mut manager = ServerManager::new().population_monitor(monitor);
+    ///
+    ///     let client = ReconnectingClient::new("10.0.0.1:37015", "password123")
+    ///         .enable_console_logs(true);
+    ///     manager.add("eu-1", client);
+    ///
+    ///     loop {
+    ///         if let (label, ServerEvent::PopulationCrossed { direction, .. }) =
+    ///             manager.next_event().await
+    ///         {
+    ///             match direction {
+    ///                 CrossingDirection::Up => println!("{} has players", label),
+    ///                 CrossingDirection::Down => println!("{} is empty", label),
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn population_monitor(mut self, monitor: PopulationMonitor) -> Self {
+        assert!(
+            !monitor.interval().is_zero(),
+            "population monitor interval must be more than 0"
+        );
+        self.population_monitor = Some(monitor);
+        self
+    }
+
+    /// Start connecting to a server.
+    ///
+    /// If a server with the same label already exists it's removed first.
+    pub fn add(&mut self, label: L, client: ReconnectingClient) {
+        self.remove(&label);
+
+        let status = Arc::new(Mutex::new(ServerStatus::Connecting));
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_server(
+            label.clone(),
+            client,
+            status.clone(),
+            requests_rx,
+            SessionState {
+                console_logs: false,
+                log_watchdog: self.log_watchdog,
+                population: self.population_monitor.clone(),
+            },
+            self.subscribers.clone(),
+        ));
+
+        self.order.push(label.clone());
+        self.servers.insert(
+            label,
+            ServerHandle {
+                status,
+                requests: requests_tx,
+                task,
+            },
+        );
+    }
+
+    /// Disconnect from a server and forget about it.
+    ///
+    /// Returns whether a server with the label existed. Events the server sent before it was
+    /// removed may still be returned by [`next_event`].
+    ///
+    /// [`next_event`]: ServerManager::next_event
+    pub fn remove(&mut self, label: &L) -> bool {
+        match self.servers.remove(label) {
+            Some(handle) => {
+                self.order.retain(|l| l != label);
+                handle.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The labels of all servers, in the order they were added.
+    pub fn labels(&self) -> impl Iterator<Item = &L> {
+        self.order.iter()
+    }
+
+    /// Get the current status of a server.
+    pub fn status(&self, label: &L) -> Option<ServerStatus> {
+        let handle = self.servers.get(label)?;
+        let status = handle.status.lock().unwrap().clone();
+        Some(status)
+    }
+
+    /// Execute a command on a server.
+    ///
+    /// See [`ClientWrite::exec_command`].
+    pub fn exec_command(&self, label: &L, cmd: &str) -> Result<(), ManagerError> {
+        self.send(label, QueuedRequest::ExecCommand(cmd.to_string()))
+    }
+
+    /// Set the value of a ConVar on a server.
+    ///
+    /// See [`ClientWrite::set_value`].
+    pub fn set_value(&self, label: &L, var: &str, val: &str) -> Result<(), ManagerError> {
+        self.send(
+            label,
+            QueuedRequest::SetValue(var.to_string(), val.to_string()),
+        )
+    }
+
+    /// Enable console logs on a server.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub fn enable_console_logs(&self, label: &L) -> Result<(), ManagerError> {
+        self.send(label, QueuedRequest::EnableConsoleLogs)
+    }
+
+    /// Execute a command on every server at once, waiting up to `timeout` for it to be sent to
+    /// each one.
+    ///
+    /// Returns whether the command was sent to each server, in the order the servers were added. A
+    /// server that doesn't send it in time, such as because it's reconnecting, gets
+    /// [`ManagerError::TimedOut`], but still sends it once it's connected.
+    ///
+    /// The command's output arrives as console logs from each server, which a [`CommandEcho`]
+    /// per server can pick out.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{ReconnectingClient, ServerManager};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut manager = ServerManager::new();
+    ///     for (label, addr) in [("eu-1", "10.0.0.1:37015"), ("us-1", "10.0.1.1:37015")] {
+    ///         manager.add(label, ReconnectingClient::new(addr, "password123"));
+    ///     }
+    ///
+    ///     let results = manager
+    ///         .exec_command_all("ns_should_return_to_lobby 0", Duration::from_secs(5))
+    ///         .await;
+    ///     for (label, err) in results.failures() {
+    ///         println!("{}: {}", label, err);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`CommandEcho`]: crate::CommandEcho
+    pub async fn exec_command_all(&self, cmd: &str, timeout: Duration) -> FleetResult<L> {
+        self.send_all(QueuedRequest::ExecCommand(cmd.to_string()), timeout)
+            .await
+    }
+
+    /// Set the value of a ConVar on every server at once, waiting up to `timeout` for it to be
+    /// sent to each one.
+    ///
+    /// See [`exec_command_all`](ServerManager::exec_command_all).
+    pub async fn set_value_all(&self, var: &str, val: &str, timeout: Duration) -> FleetResult<L> {
+        let request = QueuedRequest::SetValue(var.to_string(), val.to_string());
+        self.send_all(request, timeout).await
+    }
+
+    /// Wait for the next event from any server.
+    ///
+    /// Events are buffered from when the manager is created, up to the
+    /// [subscriber capacity](ServerManager::subscriber_capacity). If this isn't called often
+    /// enough to keep up, the oldest events are dropped instead of the buffer growing, and counted
+    /// by [`lagged`](ServerManager::lagged).
+    pub async fn next_event(&mut self) -> (L, ServerEvent) {
+        loop {
+            // The manager holds a sender, so the channel never closes
+            if let Some(SubscriberEvent::Event(label, event)) = self.events.recv().await {
+                return (label, event);
+            }
+        }
+    }
+
+    /// How many events [`next_event`](ServerManager::next_event) has missed in total by falling
+    /// behind.
+    pub fn lagged(&self) -> u64 {
+        self.events.lagged()
+    }
+
+    /// Receive the events from every server separately from [`next_event`], such as to pass them
+    /// to more than one consumer.
+    ///
+    /// The subscriber receives events that happen after it's created. Like [`next_event`], a
+    /// subscriber that's slow to read drops events instead of buffering them without limit.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::{ReconnectingClient, ServerManager, SubscriberEvent};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut manager = ServerManager::new().subscriber_capacity(256);
+    ///     let mut subscriber = manager.subscribe();
+    ///
+    ///     let client = ReconnectingClient::new("10.0.0.1:37015", "password123");
+    ///     manager.add("eu-1", client);
+    ///
+    ///     while let Some(event) = subscriber.recv().await {
+    ///         match event {
+    ///             SubscriberEvent::Event(label, event) => println!("[{}] {:?}", label, event),
+    ///             SubscriberEvent::Lagged(count) => println!("Missed {} events", count),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`next_event`]: ServerManager::next_event
+    pub fn subscribe(&self) -> ServerSubscriber<L> {
+        ServerSubscriber {
+            events: self.subscribers.subscribe(),
+            lagged: 0,
+        }
+    }
+
+    fn send(&self, label: &L, request: QueuedRequest) -> Result<(), ManagerError> {
+        let handle = self.servers.get(label).ok_or(ManagerError::UnknownServer)?;
+        handle
+            .requests
+            .send((request, None))
+            .map_err(|_| ManagerError::Stopped)
+    }
+
+    async fn send_all(&self, request: QueuedRequest, timeout: Duration) -> FleetResult<L> {
+        // Every server is sent the request before waiting on any of them, so they all share the
+        // same deadline
+        let deadline = Instant::now() + timeout;
+        let pending: Vec<_> = self
+            .order
+            .iter()
+            .map(|label| {
+                let handle = &self.servers[label];
+                let (sent_tx, sent_rx) = oneshot::channel();
+                let queued = handle.requests.send((request.clone(), Some(sent_tx)));
+                (label.clone(), queued.map(|()| sent_rx))
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(pending.len());
+        for (label, queued) in pending {
+            let result = match queued {
+                Ok(sent) => match tokio::time::timeout_at(deadline, sent).await {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(_)) => Err(ManagerError::Disconnected),
+                    Err(_) => Err(ManagerError::TimedOut),
+                },
+                Err(_) => Err(ManagerError::Stopped),
+            };
+            results.push((label, result));
+        }
+        results.into_iter().collect()
+    }
+}
+
+impl<L: Clone + Eq + Hash + Send + Sync + 'static> Default for ServerManager<L> {
+    fn default() -> Self {
+        ServerManager::new()
+    }
+}
+
+impl<L: Clone> ServerSubscriber<L> {
+    /// Wait for the next event from any server.
+    ///
+    /// Returns `None` once the manager has been dropped and every event before that has been
+    /// received.
+    pub async fn recv(&mut self) -> Option<SubscriberEvent<L>> {
+        match self.events.recv().await {
+            Ok((label, event)) => Some(SubscriberEvent::Event(label, event)),
+            Err(RecvError::Lagged(count)) => {
+                self.lagged += count;
+                Some(SubscriberEvent::Lagged(count))
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    /// How many events this subscriber has missed in total by falling behind.
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+}
+
+impl<L> Drop for ServerManager<L> {
+    fn drop(&mut self) {
+        for handle in self.servers.values() {
+            handle.task.abort();
+        }
+    }
+}
+
+async fn run_server<L: Clone + Sync>(
+    label: L,
+    client: ReconnectingClient,
+    status: Arc<Mutex<ServerStatus>>,
+    mut requests: mpsc::UnboundedReceiver<Message>,
+    mut session: SessionState,
+    subscribers: broadcast::Sender<(L, ServerEvent)>,
+) {
+    let send_event = |event: ServerEvent| {
+        // The manager may have been dropped, in which case nobody is listening
+        let _ = subscribers.send((label.clone(), event));
+    };
+
+    let mut reconnecting = false;
+    loop {
+        let mut refused = false;
+        let connected = client
+            .connect_reporting(|err| refused |= is_connection_refused(err))
+            .await;
+        let (read, mut write) = match connected {
+            Ok(halves) => halves,
+            Err(err) => {
+                let err = Arc::new(err);
+                *status.lock().unwrap() = ServerStatus::Failed(err.clone());
+                send_event(ServerEvent::Failed(err));
+                return;
+            }
+        };
+
+        // Restore what was set on the connection before it was lost
+        if reconnecting && session.console_logs && !client.enables_console_logs() {
+            if let Err(err) = write.enable_console_logs().await {
+                send_event(ServerEvent::Disconnected(Arc::new(err)));
+                continue;
+            }
+        }
+
+        *status.lock().unwrap() = ServerStatus::Connected;
+        send_event(ServerEvent::Connected);
+        if reconnecting && refused {
+            send_event(ServerEvent::Restarted);
+        }
+        reconnecting = true;
+
+        if client.enables_console_logs() {
+            session.console_logs = true;
+        }
+
+        let err = run_session(read, write, &mut requests, &mut session, &send_event).await;
+        let err = Arc::new(err);
+        *status.lock().unwrap() = ServerStatus::Reconnecting {
+            last_error: err.clone(),
+        };
+        send_event(ServerEvent::Disconnected(err));
+    }
+}
+
+#[derive(Debug)]
+struct SessionState {
+    // Whether console logs should be sent, to enable them again after reconnecting
+    console_logs: bool,
+    log_watchdog: Option<Duration>,
+    population: Option<PopulationMonitor>,
+}
+
+// Runs until the connection fails, returning the error.
+async fn run_session(
+    mut read: ClientRead,
+    mut write: ClientWrite,
+    requests: &mut mpsc::UnboundedReceiver<Message>,
+    session: &mut SessionState,
+    send_event: &impl Fn(ServerEvent),
+) -> crate::Error {
+    let mut last_line = Instant::now();
+    let mut probe_sent: Option<Instant> = None;
+    let mut next_sample = Instant::now();
+
+    loop {
+        let watchdog = session.log_watchdog.filter(|_| session.console_logs);
+        let check_at = match (watchdog, probe_sent) {
+            (Some(_), Some(sent)) => sent + LOG_WATCHDOG_GRACE,
+            (Some(silence), None) => last_line + silence,
+            (None, _) => Instant::now() + Duration::from_secs(3600),
+        };
+
+        let result = tokio::select! {
+            line = read.receive_console_log() => {
+                last_line = Instant::now();
+                probe_sent = None;
+                line.map(|line| {
+                    let crossings = match &mut session.population {
+                        Some(population) => population.observe(&line),
+                        None => Vec::new(),
+                    };
+                    send_event(ServerEvent::ConsoleLog(line));
+                    for crossed in crossings {
+                        send_event(ServerEvent::PopulationCrossed {
+                            threshold: crossed.threshold,
+                            direction: crossed.direction,
+                        });
+                    }
+                })
+            }
+            _ = tokio::time::sleep_until(next_sample), if session.population.is_some() => {
+                let interval = session.population.as_ref().unwrap().interval();
+                next_sample = Instant::now() + interval;
+                write.exec_command("status").await
+            }
+            _ = tokio::time::sleep_until(check_at), if watchdog.is_some() => {
+                match probe_sent {
+                    // Nothing was printed, so console logs must be disabled
+                    Some(_) => {
+                        probe_sent = None;
+                        last_line = Instant::now();
+                        let enabled = write.enable_console_logs().await;
+                        enabled.map(|()| send_event(ServerEvent::ConsoleLogsReenabled))
+                    }
+                    None => {
+                        probe_sent = Some(Instant::now());
+                        write.exec_command("sv_rcon_sendlogs").await
+                    }
+                }
+            }
+            Some((request, sent)) = requests.recv() => {
+                let result = match request {
+                    QueuedRequest::ExecCommand(cmd) => write.exec_command(&cmd).await,
+                    QueuedRequest::SetValue(var, val) => write.set_value(&var, &val).await,
+                    QueuedRequest::EnableConsoleLogs => {
+                        session.console_logs = true;
+                        write.enable_console_logs().await
+                    }
+                };
+                if let (Ok(()), Some(sent)) = (&result, sent) {
+                    let _ = sent.send(());
+                }
+                result
+            }
+        };
+
+        if let Err(err) = result {
+            return err;
+        }
+    }
+}
+
+fn is_connection_refused(err: &crate::Error) -> bool {
+    matches!(err, crate::Error::Io(err) if err.kind() == std::io::ErrorKind::ConnectionRefused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{hold_clock, request_type, TestConnection, TestServer};
+    use northstar_rcon_types::raw::Request_t;
+    use tokio::time::advance;
+
+    const SILENCE: Duration = Duration::from_secs(30);
+    const SECOND: Duration = Duration::from_secs(1);
+
+    // Connect a manager with the log watchdog to the server
+    async fn connect(
+        server: &TestServer,
+        console_logs: bool,
+    ) -> (ServerManager<&'static str>, TestConnection) {
+        let mut manager = ServerManager::new().log_watchdog(SILENCE);
+        let client = ReconnectingClient::new(server.addr().to_string(), "password123")
+            .enable_console_logs(console_logs);
+        manager.add("test", client);
+
+        let mut connection = server.accept_authenticated().await;
+        if console_logs {
+            let request = connection.receive().await;
+            assert_eq!(
+                request_type(&request),
+                Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG
+            );
+        }
+        assert!(matches!(
+            manager.next_event().await,
+            ("test", ServerEvent::Connected)
+        ));
+        (manager, connection)
+    }
+
+    async fn expect_log(manager: &mut ServerManager<&'static str>) {
+        assert!(matches!(
+            manager.next_event().await,
+            (_, ServerEvent::ConsoleLog(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn log_watchdog() {
+        let _clock = hold_clock();
+        let server = TestServer::bind().await;
+        let (mut manager, mut connection) = connect(&server, true).await;
+
+        // The server is only probed once it's been quiet for long enough
+        connection.send_log("hello").await;
+        expect_log(&mut manager).await;
+        advance(SILENCE - SECOND).await;
+        connection.assert_idle().await;
+        advance(SECOND).await;
+        assert_eq!(connection.receive_command().await, "sv_rcon_sendlogs");
+
+        // Console logs are still on, so the probe is answered and nothing is enabled
+        connection.send_log("\"sv_rcon_sendlogs\" = \"1\"").await;
+        expect_log(&mut manager).await;
+        advance(SILENCE - SECOND).await;
+        connection.assert_idle().await;
+        advance(SECOND).await;
+        assert_eq!(connection.receive_command().await, "sv_rcon_sendlogs");
+
+        // Until they're turned off, when the probe goes unanswered for the grace period
+        advance(LOG_WATCHDOG_GRACE - SECOND).await;
+        connection.assert_idle().await;
+        advance(SECOND).await;
+        let request = connection.receive().await;
+        assert_eq!(
+            request_type(&request),
+            Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG
+        );
+        assert!(matches!(
+            manager.next_event().await,
+            ("test", ServerEvent::ConsoleLogsReenabled)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn log_watchdog_ignores_servers_without_logs() {
+        let _clock = hold_clock();
+        let server = TestServer::bind().await;
+        let (_manager, mut connection) = connect(&server, false).await;
+
+        advance(SILENCE * 10).await;
+        connection.assert_idle().await;
+    }
+
+    #[test]
+    #[should_panic(expected = "interval must be more than 0")]
+    fn population_monitor_without_interval() {
+        let _ =
+            ServerManager::<&str>::new().population_monitor(PopulationMonitor::new(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn fleet_results_in_order_added() {
+        let server = TestServer::bind().await;
+        let mut manager = ServerManager::new();
+        for label in ["c", "a", "d", "b", "e"] {
+            manager.add(
+                label,
+                ReconnectingClient::new(server.addr().to_string(), "pw"),
+            );
+        }
+        manager.remove(&"d");
+        manager.add(
+            "a",
+            ReconnectingClient::new(server.addr().to_string(), "pw"),
+        );
+
+        let results = manager.exec_command_all("status", Duration::ZERO).await;
+        let labels: Vec<_> = results.iter().map(|(label, _)| *label).collect();
+        assert_eq!(labels, ["c", "b", "e", "a"]);
+        assert!(manager.labels().eq(&labels));
+    }
+}
@@ -0,0 +1,77 @@
+/// Maps that come with Northstar, and the names they're shown with in game.
+pub const KNOWN_MAPS: &[(&str, &str)] = &[
+    ("mp_angel_city", "Angel City"),
+    ("mp_black_water_canal", "Black Water Canal"),
+    ("mp_box", "Box"),
+    ("mp_coliseum", "The Coliseum"),
+    ("mp_coliseum_column", "Pillars"),
+    ("mp_colony02", "Colony"),
+    ("mp_complex3", "Complex"),
+    ("mp_crashsite3", "Crash Site"),
+    ("mp_drydock", "Drydock"),
+    ("mp_eden", "Eden"),
+    ("mp_forwardbase_kodai", "Forwardbase Kodai"),
+    ("mp_glitch", "Glitch"),
+    ("mp_grave", "Boomtown"),
+    ("mp_homestead", "Homestead"),
+    ("mp_lf_deck", "Deck"),
+    ("mp_lf_meadow", "Meadow"),
+    ("mp_lf_stacks", "Stacks"),
+    ("mp_lf_township", "Township"),
+    ("mp_lf_traffic", "Traffic"),
+    ("mp_lf_uma", "UMA"),
+    ("mp_lobby", "Lobby"),
+    ("mp_relic02", "Relic"),
+    ("mp_rise", "Rise"),
+    ("mp_thaw", "Exoplanet"),
+    ("mp_wargames", "War Games"),
+];
+
+/// A map a server can load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    /// The name used to load the map, like `mp_glitch`.
+    pub name: String,
+
+    /// The name the map is shown with in game, like `Glitch`, if it's a known map.
+    pub display_name: Option<&'static str>,
+}
+
+/// The in-game name of a map, like `Glitch` for `mp_glitch`, if it's a known map.
+pub fn map_display_name(name: &str) -> Option<&'static str> {
+    KNOWN_MAPS
+        .iter()
+        .find(|(known_name, _)| *known_name == name)
+        .map(|(_, display_name)| *display_name)
+}
+
+/// List the maps a server can load: the ones that come with Northstar, followed by any others
+/// the server has reported, such as the current map from [`ServerInfo`](crate::ServerInfo).
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::maps;
+///
+/// let maps = maps(["mp_glitch", "mp_custom_arena"]);
+/// assert!(maps.iter().any(|map| map.display_name == Some("Glitch")));
+/// assert_eq!(maps.last().unwrap().name, "mp_custom_arena");
+/// ```
+pub fn maps<'a>(reported: impl IntoIterator<Item = &'a str>) -> Vec<Map> {
+    let mut maps: Vec<Map> = KNOWN_MAPS
+        .iter()
+        .map(|&(name, display_name)| Map {
+            name: name.to_string(),
+            display_name: Some(display_name),
+        })
+        .collect();
+
+    for name in reported {
+        if !maps.iter().any(|map| map.name == name) {
+            maps.push(Map {
+                name: name.to_string(),
+                display_name: None,
+            });
+        }
+    }
+    maps
+}
@@ -0,0 +1,112 @@
+use crate::ServerInfo;
+use std::time::Duration;
+
+/// Which way a player count moved past a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossingDirection {
+    /// The count rose to the threshold or above.
+    Up,
+
+    /// The count fell below the threshold.
+    Down,
+}
+
+/// A player count that moved past one of a [`PopulationMonitor`]'s thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PopulationCrossed {
+    pub threshold: u32,
+    pub direction: CrossingDirection,
+}
+
+/// Tracks a server's player count from `status` output, and reports when it crosses
+/// thresholds.
+///
+/// The monitor only reads lines: something else has to run `status` every
+/// [`interval`](PopulationMonitor::interval) and pass the console log lines to
+/// [`observe`](PopulationMonitor::observe). [`ServerManager::population_monitor`] does this for
+/// every server it owns.
+///
+/// Only human players are counted. The first count read sets the starting point without
+/// reporting any crossings.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::{CrossingDirection, PopulationMonitor};
+/// use std::time::Duration;
+///
+/// let mut monitor = PopulationMonitor::new(Duration::from_secs(30))
+///     .threshold(1)
+///     .threshold(8);
+///
+/// monitor.observe("players : 0 humans, 0 bots (16 max)");
+/// let crossed = monitor.observe("players : 9 humans, 0 bots (16 max)");
+/// assert_eq!(crossed.len(), 2);
+/// assert!(crossed.iter().all(|c| c.direction == CrossingDirection::Up));
+/// ```
+///
+/// [`ServerManager::population_monitor`]: crate::ServerManager::population_monitor
+#[derive(Debug, Clone)]
+pub struct PopulationMonitor {
+    interval: Duration,
+    thresholds: Vec<u32>,
+    last_count: Option<u32>,
+}
+
+impl PopulationMonitor {
+    /// Create a monitor with no thresholds, for sampling every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        PopulationMonitor {
+            interval,
+            thresholds: Vec::new(),
+            last_count: None,
+        }
+    }
+
+    /// Report when the player count rises to `players` or above, or falls back below it.
+    pub fn threshold(mut self, players: u32) -> Self {
+        self.thresholds.push(players);
+        self.thresholds.sort_unstable();
+        self.thresholds.dedup();
+        self
+    }
+
+    /// How often the player count should be sampled.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The last player count read, if there has been one.
+    pub fn last_count(&self) -> Option<u32> {
+        self.last_count
+    }
+
+    /// Read a console log line, returning the thresholds crossed if it's the player count from
+    /// `status` output. Lines that aren't are ignored.
+    pub fn observe(&mut self, line: &str) -> Vec<PopulationCrossed> {
+        let mut info = ServerInfo::default();
+        info.update(line);
+        let count = match info.players {
+            Some(count) => count,
+            None => return Vec::new(),
+        };
+        let last_count = match self.last_count.replace(count) {
+            Some(last_count) => last_count,
+            None => return Vec::new(),
+        };
+
+        self.thresholds
+            .iter()
+            .filter_map(|&threshold| {
+                let direction = match (last_count >= threshold, count >= threshold) {
+                    (false, true) => CrossingDirection::Up,
+                    (true, false) => CrossingDirection::Down,
+                    _ => return None,
+                };
+                Some(PopulationCrossed {
+                    threshold,
+                    direction,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,4 +0,0 @@
-include!(concat!(env!("OUT_DIR"), "/protos/mod.rs"));
-
-pub use self::cl_rcon::*;
-pub use self::sv_rcon::*;
@@ -0,0 +1,192 @@
+//! Records of every frame sent and received, for comparing how servers behave.
+//!
+//! This module requires the `protocol-trace` feature.
+
+use northstar_rcon_types::raw;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// Which way a frame went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A frame sent or received, with its decoded fields.
+///
+/// Passwords in authentication requests are replaced with `<redacted>`. Formatting with `{}`
+/// writes the record on one line without anything that changes between runs, like the time, so
+/// traces from two servers can be diffed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameRecord {
+    pub direction: Direction,
+
+    /// The request or response type number, if the frame had one.
+    pub frame_type: Option<i32>,
+
+    /// The length of the encoded frame, not counting its length prefix.
+    pub len: usize,
+    pub id: Option<i32>,
+    pub buf: Option<String>,
+    pub val: Option<String>,
+
+    /// The numbers of fields the protobuf definitions don't have, such as ones added by a newer
+    /// server.
+    pub unknown_fields: Vec<u32>,
+}
+
+impl FrameRecord {
+    /// The name of the frame's type, if it's one this crate knows about.
+    pub fn type_name(&self) -> Option<&'static str> {
+        let name = match (self.direction, self.frame_type?) {
+            (Direction::Sent, 0) => "SERVERDATA_REQUEST_VALUE",
+            (Direction::Sent, 1) => "SERVERDATA_REQUEST_SETVALUE",
+            (Direction::Sent, 2) => "SERVERDATA_REQUEST_EXECCOMMAND",
+            (Direction::Sent, 3) => "SERVERDATA_REQUEST_AUTH",
+            (Direction::Sent, 4) => "SERVERDATA_REQUEST_SEND_CONSOLE_LOG",
+            (Direction::Sent, 5) => "SERVERDATA_REQUEST_SEND_REMOTEBUG",
+            (Direction::Received, 0) => "SERVERDATA_RESPONSE_VALUE",
+            (Direction::Received, 1) => "SERVERDATA_RESPONSE_UPDATE",
+            (Direction::Received, 2) => "SERVERDATA_RESPONSE_AUTH",
+            (Direction::Received, 3) => "SERVERDATA_RESPONSE_CONSOLE_LOG",
+            (Direction::Received, 4) => "SERVERDATA_RESPONSE_STRING",
+            (Direction::Received, 5) => "SERVERDATA_RESPONSE_REMOTEBUG",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    pub(crate) fn sent(request: &raw::Request, len: usize) -> Self {
+        let frame_type = request.requestType.map(|t| t.value());
+
+        // Never record passwords
+        let buf = match frame_type {
+            Some(3) => request
+                .requestBuf
+                .as_ref()
+                .map(|_| "<redacted>".to_string()),
+            _ => request.requestBuf.clone(),
+        };
+
+        FrameRecord {
+            direction: Direction::Sent,
+            frame_type,
+            len,
+            id: request.requestID,
+            buf,
+            val: request.requestVal.clone(),
+            unknown_fields: unknown_field_numbers(&request.special_fields),
+        }
+    }
+
+    pub(crate) fn received(response: &raw::Response, len: usize) -> Self {
+        FrameRecord {
+            direction: Direction::Received,
+            frame_type: response.responseType.map(|t| t.value()),
+            len,
+            id: response.responseID,
+            buf: response.responseBuf.clone(),
+            val: response.responseVal.clone(),
+            unknown_fields: unknown_field_numbers(&response.special_fields),
+        }
+    }
+}
+
+impl Display for FrameRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            Direction::Sent => "sent",
+            Direction::Received => "received",
+        };
+        match (self.type_name(), self.frame_type) {
+            (Some(name), _) => write!(f, "{} {}", direction, name)?,
+            (None, Some(frame_type)) => write!(f, "{} type {}", direction, frame_type)?,
+            (None, None) => write!(f, "{} untyped", direction)?,
+        }
+        write!(
+            f,
+            " len={} id={:?} buf={:?} val={:?}",
+            self.len, self.id, self.buf, self.val
+        )?;
+        if !self.unknown_fields.is_empty() {
+            write!(f, " unknown_fields={:?}", self.unknown_fields)?;
+        }
+        Ok(())
+    }
+}
+
+fn unknown_field_numbers(special_fields: &protobuf::SpecialFields) -> Vec<u32> {
+    let fields = special_fields.unknown_fields().iter();
+    fields.map(|(field, _)| field).collect()
+}
+
+// The callback a connection passes its frame records to
+#[derive(Clone)]
+pub(crate) struct Tracer(Arc<dyn Fn(&FrameRecord) + Send + Sync>);
+
+impl Tracer {
+    pub fn new(callback: impl Fn(&FrameRecord) + Send + Sync + 'static) -> Self {
+        Tracer(Arc::new(callback))
+    }
+
+    pub fn record(&self, record: &FrameRecord) {
+        (self.0)(record)
+    }
+}
+
+impl std::fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Tracer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Request;
+
+    #[test]
+    fn display() {
+        let request = raw::Request::from(Request::ExecCommand { cmd: "status" });
+        assert_eq!(
+            FrameRecord::sent(&request, 12).to_string(),
+            "sent SERVERDATA_REQUEST_EXECCOMMAND len=12 id=Some(-1) buf=Some(\"status\") val=None"
+        );
+
+        let mut response = raw::Response::new();
+        response.responseID = Some(-1);
+        response.responseType = Some(protobuf::EnumOrUnknown::from_i32(9));
+        assert_eq!(
+            FrameRecord::received(&response, 4).to_string(),
+            "received type 9 len=4 id=Some(-1) buf=None val=None"
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        let mut request = raw::Request::new();
+        request.requestType = Some(raw::Request_t::SERVERDATA_REQUEST_SEND_REMOTEBUG.into());
+        assert_eq!(
+            FrameRecord::sent(&request, 2).type_name(),
+            Some("SERVERDATA_REQUEST_SEND_REMOTEBUG")
+        );
+
+        let mut response = raw::Response::new();
+        response.responseType = Some(raw::Response_t::SERVERDATA_RESPONSE_REMOTEBUG.into());
+        assert_eq!(
+            FrameRecord::received(&response, 2).type_name(),
+            Some("SERVERDATA_RESPONSE_REMOTEBUG")
+        );
+    }
+
+    #[test]
+    fn password_redacted() {
+        let request = raw::Request::from(Request::Auth {
+            pass: "password123",
+        });
+        let record = FrameRecord::sent(&request, 17);
+        assert_eq!(record.buf.as_deref(), Some("<redacted>"));
+        assert!(!record.to_string().contains("password123"));
+    }
+}
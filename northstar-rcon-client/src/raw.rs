@@ -0,0 +1,38 @@
+//! Raw protocol messages, for fields the high-level API doesn't expose.
+//!
+//! These are the types generated from the server's protobuf definitions. They can be sent and
+//! received with [`ClientWrite::send_raw`] and [`ClientRead::receive_raw`].
+//!
+//! This module requires the `raw-protocol` feature. The generated types may change between
+//! versions of this crate if the server's protocol changes.
+//!
+//! Fields a newer server sends that the definitions don't have yet are kept in each message's
+//! `special_fields`, and can be read with `special_fields.unknown_fields()`. Everything else in
+//! this crate ignores them.
+//!
+//! # Example
+//! ```rust,no_run
+//! use northstar_rcon_client::connect;
+//! use northstar_rcon_client::raw::{Request, Request_t};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = connect("localhost:37015").await.unwrap();
+//!     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+//!
+//!     let mut request = Request::new();
+//!     request.requestID = Some(42);
+//!     request.requestType = Some(Request_t::SERVERDATA_REQUEST_VALUE.into());
+//!     request.requestBuf = Some("sv_cheats".to_string());
+//!     write.send_raw(request).await.unwrap();
+//!
+//!     let response = read.receive_raw().await.unwrap();
+//!     println!("{:?}", response);
+//! }
+//! ```
+//!
+//! [`ClientWrite::send_raw`]: crate::ClientWrite::send_raw
+//! [`ClientRead::receive_raw`]: crate::ClientRead::receive_raw
+
+pub use northstar_rcon_types::protobuf::{UnknownFields, UnknownValueRef};
+pub use northstar_rcon_types::raw::{Request, Request_t, Response, Response_t};
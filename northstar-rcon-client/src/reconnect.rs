@@ -0,0 +1,329 @@
+use crate::{AuthError, AuthMatchers, ClientRead, ClientWrite, CommandFilter};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long to wait between connection attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// Always wait the same amount of time.
+    Fixed(Duration),
+
+    /// Start with an initial delay and double it after every failed attempt, up to a maximum.
+    ///
+    /// If `jitter` is enabled each delay is randomly shortened by up to half, so many clients
+    /// reconnecting at once don't all retry at the same time.
+    Exponential {
+        initial: Duration,
+        max: Duration,
+        jitter: bool,
+    },
+}
+
+impl Default for Backoff {
+    /// Exponential backoff with jitter, starting at 1 second and capped at 1 minute.
+    fn default() -> Self {
+        Backoff::Exponential {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl Backoff {
+    /// Get the delay before retrying, after this many failed attempts.
+    pub fn delay(&self, failed_attempts: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                max,
+                jitter,
+            } => {
+                let multiplier = 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+                let delay = initial.saturating_mul(multiplier).min(max);
+
+                if jitter {
+                    delay / 2 + delay.mul_f64(random_fraction() / 2.)
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+/// An error describing why reconnecting failed for good.
+#[derive(Debug, thiserror::Error)]
+pub enum ReconnectError {
+    /// Authentication failed because an invalid password was used.
+    #[error("invalid password")]
+    InvalidPassword,
+
+    /// Authentication failed because this user or IP address is banned.
+    #[error("banned")]
+    Banned,
+
+    /// The maximum number of attempts or the maximum duration was reached.
+    #[error("gave up after {attempts} attempts: {source}")]
+    GaveUp {
+        /// How many connection attempts were made.
+        attempts: u32,
+
+        /// The error from the last attempt.
+        #[source]
+        source: crate::Error,
+    },
+}
+
+/// Connects and authenticates with an RCON server, retrying when connecting fails.
+///
+/// Connection and protocol errors are retried according to a [`Backoff`] strategy, optionally
+/// giving up after a number of attempts or after a total duration. Authentication failures are
+/// never retried, since the same password would fail again.
+///
+/// Call [`connect`] again after the connection drops to reconnect.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::{Backoff, ReconnectingClient};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = ReconnectingClient::new("localhost:37015", "password123")
+///         .backoff(Backoff::Fixed(Duration::from_secs(5)))
+///         .max_attempts(10)
+///         .enable_console_logs(true);
+///
+///     loop {
+///         let (mut read, _write) = client.connect().await.unwrap();
+///
+///         while let Ok(line) = read.receive_console_log().await {
+///             println!("> {}", line);
+///         }
+///
+///         println!("Connection lost, reconnecting...");
+///     }
+/// }
+/// ```
+///
+/// [`connect`]: ReconnectingClient::connect
+#[derive(Clone)]
+pub struct ReconnectingClient {
+    addr: String,
+    pass: String,
+    backoff: Backoff,
+    max_attempts: Option<u32>,
+    max_duration: Option<Duration>,
+    enable_console_logs: bool,
+    auth_matchers: AuthMatchers,
+    dry_run: bool,
+    command_filter: Option<CommandFilter>,
+    #[cfg(feature = "protocol-trace")]
+    tracer: Option<crate::protocol_trace::Tracer>,
+}
+
+// Written by hand so the password is never printed
+impl std::fmt::Debug for ReconnectingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ReconnectingClient");
+        debug
+            .field("addr", &self.addr)
+            .field("pass", &"<redacted>")
+            .field("backoff", &self.backoff)
+            .field("max_attempts", &self.max_attempts)
+            .field("max_duration", &self.max_duration)
+            .field("enable_console_logs", &self.enable_console_logs)
+            .field("auth_matchers", &self.auth_matchers)
+            .field("dry_run", &self.dry_run)
+            .field("command_filter", &self.command_filter);
+        #[cfg(feature = "protocol-trace")]
+        debug.field("tracer", &self.tracer);
+        debug.finish()
+    }
+}
+
+impl ReconnectingClient {
+    /// Create a client for a server address and password.
+    ///
+    /// By default the [default backoff](Backoff::default) is used and the client never gives up.
+    pub fn new(addr: impl Into<String>, pass: impl Into<String>) -> Self {
+        ReconnectingClient {
+            addr: addr.into(),
+            pass: pass.into(),
+            backoff: Backoff::default(),
+            max_attempts: None,
+            max_duration: None,
+            enable_console_logs: false,
+            auth_matchers: AuthMatchers::default(),
+            dry_run: false,
+            command_filter: None,
+            #[cfg(feature = "protocol-trace")]
+            tracer: None,
+        }
+    }
+
+    /// Set how long to wait between attempts.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Give up after this many failed attempts.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Give up once this much time has passed since the first attempt.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Enable console logs after every successful connection.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub fn enable_console_logs(mut self, enable: bool) -> Self {
+        self.enable_console_logs = enable;
+        self
+    }
+
+    /// Set the text used to tell why authentication failed.
+    ///
+    /// See [`NotAuthenticatedClient::auth_matchers`](crate::NotAuthenticatedClient::auth_matchers).
+    pub fn auth_matchers(mut self, matchers: AuthMatchers) -> Self {
+        self.auth_matchers = matchers;
+        self
+    }
+
+    /// Put every connection in dry-run mode, so commands and ConVar changes aren't sent.
+    ///
+    /// See [`ClientWrite::set_dry_run`].
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Check every request against a filter on every connection.
+    ///
+    /// See [`ClientWrite::set_command_filter`].
+    pub fn command_filter(mut self, filter: CommandFilter) -> Self {
+        self.command_filter = Some(filter);
+        self
+    }
+
+    /// Pass a record of every frame sent and received on every connection to `callback`.
+    ///
+    /// See [`NotAuthenticatedClient::protocol_trace`](crate::NotAuthenticatedClient::protocol_trace).
+    #[cfg(feature = "protocol-trace")]
+    pub fn protocol_trace(
+        mut self,
+        callback: impl Fn(&crate::protocol_trace::FrameRecord) + Send + Sync + 'static,
+    ) -> Self {
+        self.tracer = Some(crate::protocol_trace::Tracer::new(callback));
+        self
+    }
+
+    /// The address of the server.
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub(crate) fn enables_console_logs(&self) -> bool {
+        self.enable_console_logs
+    }
+
+    /// Connect and authenticate, retrying until successful or the client gives up.
+    pub async fn connect(&self) -> Result<(ClientRead, ClientWrite), ReconnectError> {
+        self.connect_reporting(|_| {}).await
+    }
+
+    // Like `connect`, calling `on_failed` with the error from each failed attempt
+    pub(crate) async fn connect_reporting(
+        &self,
+        mut on_failed: impl FnMut(&crate::Error),
+    ) -> Result<(ClientRead, ClientWrite), ReconnectError> {
+        let start = Instant::now();
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let err = match self.try_connect().await {
+                Ok(halves) => return Ok(halves),
+                Err(AuthError::InvalidPassword { .. }) => {
+                    return Err(ReconnectError::InvalidPassword)
+                }
+                Err(AuthError::Banned { .. }) => return Err(ReconnectError::Banned),
+                Err(AuthError::Fatal(err)) => err,
+            };
+            on_failed(&err);
+
+            let delay = self.backoff.delay(attempts);
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(attempts, %err, ?delay, "reconnecting failed");
+            let out_of_attempts = self.max_attempts.is_some_and(|max| attempts >= max);
+            let out_of_time = self
+                .max_duration
+                .is_some_and(|max| start.elapsed() + delay > max);
+            if out_of_attempts || out_of_time {
+                return Err(ReconnectError::GaveUp {
+                    attempts,
+                    source: err,
+                });
+            }
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn try_connect(&self) -> Result<(ClientRead, ClientWrite), AuthError> {
+        let client = crate::connect(self.addr.as_str())
+            .await?
+            .auth_matchers(self.auth_matchers.clone());
+        #[cfg(feature = "protocol-trace")]
+        let client = client.tracer(self.tracer.clone());
+
+        let (read, mut write) = client
+            .authenticate(&self.pass)
+            .await
+            .map_err(|(_, err)| err)?;
+
+        if self.enable_console_logs {
+            write.enable_console_logs().await?;
+        }
+        write.set_dry_run(self.dry_run);
+        write.set_command_filter(self.command_filter.clone());
+
+        Ok((read, write))
+    }
+}
+
+// Returns a random number between 0 and 1.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconnectingClient;
+
+    #[test]
+    fn debug_redacts_password() {
+        let client = ReconnectingClient::new("localhost:37015", "hunter2");
+        let debug = format!("{:?}", client);
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("pass: \"<redacted>\""));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::command::BREAK_CHARS;
+use crate::ClientWrite;
+use std::collections::HashSet;
+
+/// A writer that only sends the commands and ConVars it's been allowed to.
+///
+/// Anything else is rejected without being sent, with
+/// [`Error::PermissionDenied`](crate::Error::PermissionDenied). This is for exposing a server
+/// to people who shouldn't have full control of it, like moderators using a panel. Names are
+/// matched ignoring case, like the server does.
+///
+/// A command line can run more than one command, separated by `;` or line breaks, and each one
+/// has to be allowed. Running a ConVar's name as a command sets it, so that needs the ConVar to be
+/// allowed. Enabling console logs is always allowed.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::{connect, Error, ScopedClient};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (_, write) = client.authenticate("password123").await.unwrap();
+///
+///     let mut moderator = ScopedClient::new(write)
+///         .allow_command("kickid")
+///         .allow_command("say")
+///         .allow_convar("ns_should_return_to_lobby");
+///
+///     moderator.exec_command("kickid 2").await.unwrap();
+///     assert!(matches!(
+///         moderator.exec_command("say bye; quit").await,
+///         Err(Error::PermissionDenied(name)) if name == "quit"
+///     ));
+/// }
+/// ```
+pub struct ScopedClient {
+    write: ClientWrite,
+    commands: HashSet<String>,
+    convars: HashSet<String>,
+}
+
+impl ScopedClient {
+    /// Wrap a writer, with nothing allowed yet.
+    pub fn new(write: ClientWrite) -> Self {
+        ScopedClient {
+            write,
+            commands: HashSet::new(),
+            convars: HashSet::new(),
+        }
+    }
+
+    /// Allow running a command.
+    pub fn allow_command(mut self, name: &str) -> Self {
+        self.commands.insert(name.to_lowercase());
+        self
+    }
+
+    /// Allow setting a ConVar, with [`set_value`](ScopedClient::set_value) or by running its
+    /// name as a command.
+    pub fn allow_convar(mut self, name: &str) -> Self {
+        self.convars.insert(name.to_lowercase());
+        self
+    }
+
+    /// Execute a command remotely, if every command in it is allowed.
+    ///
+    /// See [`ClientWrite::exec_command`].
+    pub async fn exec_command(&mut self, cmd: &str) -> crate::Result<()> {
+        for name in command_names(cmd) {
+            let name = name.to_lowercase();
+            if !self.commands.contains(&name) && !self.convars.contains(&name) {
+                return Err(crate::Error::PermissionDenied(name));
+            }
+        }
+        self.write.exec_command(cmd).await
+    }
+
+    /// Set the value of a ConVar, if it's allowed.
+    ///
+    /// See [`ClientWrite::set_value`].
+    pub async fn set_value(&mut self, var: &str, val: &str) -> crate::Result<()> {
+        let name = var.to_lowercase();
+        if !self.convars.contains(&name) {
+            return Err(crate::Error::PermissionDenied(name));
+        }
+        self.write.set_value(var, val).await
+    }
+
+    /// Send a chat message to every player, if `say` is allowed.
+    ///
+    /// See [`ClientWrite::say`].
+    pub async fn say(&mut self, message: &str) -> crate::Result<()> {
+        if !self.commands.contains("say") {
+            return Err(crate::Error::PermissionDenied("say".to_string()));
+        }
+        self.write.say(message).await
+    }
+
+    /// Enable console logs being sent to RCON clients.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub async fn enable_console_logs(&mut self) -> crate::Result<()> {
+        self.write.enable_console_logs().await
+    }
+
+    /// Get the writer back, without any restrictions.
+    pub fn into_inner(self) -> ClientWrite {
+        self.write
+    }
+}
+
+/// The name of each command in a command line, which can have more than one separated by `;` or
+/// line breaks. Separators inside quotes don't count, like on the server.
+///
+/// Names end where the server's tokenizer ends them: at whitespace or any control character, or
+/// at one of the break characters `{}()':`. A break character at the start is a name on its own.
+pub(crate) fn command_names(line: &str) -> impl Iterator<Item = &str> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                commands.push(&line[start..index]);
+                start = index + 1;
+            }
+            '\r' | '\n' => {
+                commands.push(&line[start..index]);
+                start = index + 1;
+                quoted = false;
+            }
+            _ => {}
+        }
+    }
+    commands.push(&line[start..]);
+
+    commands.into_iter().filter_map(|command| {
+        let command = command.trim_start();
+        match command.strip_prefix('"') {
+            Some(rest) => rest.split('"').next().filter(|name| !name.is_empty()),
+            None => first_token(command),
+        }
+    })
+}
+
+// The server counts everything up to a space, including control characters, as whitespace
+fn is_token_space(c: char) -> bool {
+    c <= ' ' || c.is_whitespace()
+}
+
+fn first_token(command: &str) -> Option<&str> {
+    let command = command.trim_start_matches(is_token_space);
+    let first = command.chars().next()?;
+    if BREAK_CHARS.contains(&first) {
+        return Some(&command[..first.len_utf8()]);
+    }
+
+    let end = command
+        .find(|c| is_token_space(c) || BREAK_CHARS.contains(&c))
+        .unwrap_or(command.len());
+    Some(&command[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::command_names;
+
+    fn names(line: &str) -> Vec<&str> {
+        command_names(line).collect()
+    }
+
+    #[test]
+    fn names_end_at_break_characters() {
+        assert_eq!(names("quit'"), ["quit"]);
+        assert_eq!(names("quit("), ["quit"]);
+        assert_eq!(names("quit)"), ["quit"]);
+        assert_eq!(names("quit{x}"), ["quit"]);
+        assert_eq!(names("quit:1"), ["quit"]);
+        assert_eq!(names("ban(x)"), ["ban"]);
+        assert_eq!(names("(quit"), ["("]);
+    }
+
+    #[test]
+    fn names_end_at_control_characters() {
+        assert_eq!(names("quit\x01"), ["quit"]);
+        assert_eq!(names("quit\tnow"), ["quit"]);
+        assert_eq!(names("\x01quit"), ["quit"]);
+    }
+
+    #[test]
+    fn quoted_names_keep_break_characters() {
+        assert_eq!(names("\"quit\" now"), ["quit"]);
+        assert_eq!(names("\"quit'\""), ["quit'"]);
+    }
+
+    #[test]
+    fn separators_outside_quotes_split_commands() {
+        assert_eq!(names("say hi; quit"), ["say", "quit"]);
+        assert_eq!(names("say hi\nquit"), ["say", "quit"]);
+        assert_eq!(names("say hi\r\nquit"), ["say", "quit"]);
+        assert_eq!(names("say hi;;quit"), ["say", "quit"]);
+    }
+
+    #[test]
+    fn separators_inside_quotes_dont_split_commands() {
+        assert_eq!(names("say \"hi; quit\""), ["say"]);
+        assert_eq!(names("say \"a\" ; quit"), ["say", "quit"]);
+    }
+
+    #[test]
+    fn line_breaks_end_quotes() {
+        // An unbalanced quote still ends at a line break, like on the server
+        assert_eq!(names("say \"hi\nquit"), ["say", "quit"]);
+        assert_eq!(names("say \"hi; quit"), ["say"]);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ClientWrite;
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// The write end of a client that can be cloned and shared between tasks.
+///
+/// Every clone sends through the same connection, taking turns so requests are never
+/// interleaved. Created with [`ClientWrite::into_shared`].
+#[derive(Clone)]
+pub struct SharedClientWrite {
+    write: Arc<Mutex<ClientWrite>>,
+}
+
+impl ClientWrite {
+    /// Turn this writer into one that can be cloned and used from many tasks at once.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (_, write) = client.authenticate("password123").await.unwrap();
+    ///     let write = write.into_shared();
+    ///
+    ///     let announcer = write.clone();
+    ///     tokio::spawn(async move {
+    ///         announcer.exec_command("say Restarting soon").await.unwrap();
+    ///     });
+    ///
+    ///     write.exec_command("status").await.unwrap();
+    /// }
+    /// ```
+    pub fn into_shared(self) -> SharedClientWrite {
+        SharedClientWrite {
+            write: Arc::new(Mutex::new(self)),
+        }
+    }
+}
+
+impl SharedClientWrite {
+    /// Set the value of a ConVar if it exists.
+    ///
+    /// See [`ClientWrite::set_value`].
+    pub async fn set_value(&self, var: &str, val: &str) -> crate::Result<()> {
+        self.write.lock().await.set_value(var, val).await
+    }
+
+    /// Execute a command remotely.
+    ///
+    /// See [`ClientWrite::exec_command`].
+    pub async fn exec_command(&self, cmd: &str) -> crate::Result<()> {
+        self.write.lock().await.exec_command(cmd).await
+    }
+
+    /// Send a chat message to every player, from the server.
+    ///
+    /// See [`ClientWrite::say`].
+    pub async fn say(&self, message: &str) -> crate::Result<()> {
+        self.write.lock().await.say(message).await
+    }
+
+    /// Set the value of a ConVar if it exists, sending the request with a specific ID.
+    ///
+    /// See [`ClientWrite::set_value_with_id`].
+    pub async fn set_value_with_id(
+        &self,
+        var: &str,
+        val: &str,
+        request_id: i32,
+    ) -> crate::Result<()> {
+        self.write
+            .lock()
+            .await
+            .set_value_with_id(var, val, request_id)
+            .await
+    }
+
+    /// Execute a command remotely, sending the request with a specific ID.
+    ///
+    /// See [`ClientWrite::exec_command_with_id`].
+    pub async fn exec_command_with_id(&self, cmd: &str, request_id: i32) -> crate::Result<()> {
+        self.write
+            .lock()
+            .await
+            .exec_command_with_id(cmd, request_id)
+            .await
+    }
+
+    /// Enable console logs being sent to RCON clients.
+    ///
+    /// See [`ClientWrite::enable_console_logs`].
+    pub async fn enable_console_logs(&self) -> crate::Result<()> {
+        self.write.lock().await.enable_console_logs().await
+    }
+
+    /// Send a raw protocol request.
+    ///
+    /// This requires the `raw-protocol` feature. See the [`raw`](crate::raw) module.
+    #[cfg(feature = "raw-protocol")]
+    pub async fn send_raw(&self, request: crate::raw::Request) -> crate::Result<()> {
+        self.write.lock().await.send_raw(request).await
+    }
+
+    /// Wait for the other clones to finish sending, then get the writer to use on its own.
+    ///
+    /// Nothing else is sent until the guard is dropped. This is needed for anything that spans
+    /// more than one call, like sending a request and reading its automatic ID:
+    ///
+    /// ```rust,no_run
+    /// # async fn example(write: northstar_rcon_client::SharedClientWrite) {
+    /// let mut write = write.lock().await;
+    /// write.exec_command("status").await.unwrap();
+    /// println!("Sent status with ID {}", write.last_request_id());
+    /// # }
+    /// ```
+    pub async fn lock(&self) -> MutexGuard<'_, ClientWrite> {
+        self.write.lock().await
+    }
+}
+
+impl From<ClientWrite> for SharedClientWrite {
+    fn from(write: ClientWrite) -> Self {
+        write.into_shared()
+    }
+}
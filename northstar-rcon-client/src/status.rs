@@ -0,0 +1,147 @@
+use crate::{ClientRead, ClientWrite};
+use std::time::Duration;
+
+/// How long [`ClientRead::server_info`] waits for the output of `status` to end.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The headline details about a server, read from the output of its `status` command.
+///
+/// Fields the server didn't print are `None`. The player list that follows the details isn't
+/// read.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::ServerInfo;
+///
+/// let mut info = ServerInfo::default();
+/// for line in [
+///     "hostname: Northstar Server",
+///     "map     : mp_glitch at: 0 x, 0 y, 0 z",
+///     "players : 3 humans, 0 bots (16 max)",
+/// ] {
+///     info.update(line);
+/// }
+///
+/// assert_eq!(info.map.as_deref(), Some("mp_glitch"));
+/// assert_eq!((info.players, info.max_players), (Some(3), Some(16)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub hostname: Option<String>,
+    pub map: Option<String>,
+
+    /// The number of human players.
+    pub players: Option<u32>,
+    pub max_players: Option<u32>,
+}
+
+impl ServerInfo {
+    /// Update the details from a line of `status` output. Returns whether the line had one of
+    /// the details.
+    pub fn update(&mut self, line: &str) -> bool {
+        let (field, value) = match strip_tags(line).split_once(':') {
+            Some((field, value)) => (field.trim(), value.trim()),
+            None => return false,
+        };
+
+        match field {
+            "hostname" => self.hostname = Some(value.to_string()),
+
+            // e.g. `map     : mp_glitch at: 0 x, 0 y, 0 z`
+            "map" => self.map = value.split_whitespace().next().map(str::to_string),
+
+            // e.g. `players : 3 humans, 0 bots (16 max)`
+            "players" => {
+                self.players = value.split_whitespace().next().and_then(|n| n.parse().ok());
+                self.max_players = value
+                    .split_once('(')
+                    .and_then(|(_, max)| max.split_whitespace().next())
+                    .and_then(|max| max.parse().ok());
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl ClientRead {
+    /// Run `status` with `write`, and read the server's details from its output.
+    ///
+    /// The output is read from the console logs, so they need to be enabled. Only the lines from
+    /// the `hostname` line that starts the output to the `#end` that closes it are read. Other
+    /// console log lines received in the meantime are skipped, though they're still kept in the
+    /// [history](ClientRead::history).
+    ///
+    /// If the output hasn't ended within 10 seconds, such as because console logs aren't enabled,
+    /// this fails with an IO error of kind [`TimedOut`](std::io::ErrorKind::TimedOut).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use northstar_rcon_client::connect;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = connect("localhost:37015").await.unwrap();
+    ///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+    ///     write.enable_console_logs().await.unwrap();
+    ///
+    ///     let info = read.server_info(&mut write).await.unwrap();
+    ///     println!("{:?} on {:?}", info.hostname, info.map);
+    /// }
+    /// ```
+    pub async fn server_info(&mut self, write: &mut ClientWrite) -> crate::Result<ServerInfo> {
+        write.exec_command("status").await?;
+        let deadline = tokio::time::Instant::now() + STATUS_TIMEOUT;
+
+        // The output starts with the hostname, and ends with the player list, which is closed
+        // with `#end`
+        let mut info = ServerInfo::default();
+        let mut started = false;
+        let mut in_players = false;
+        loop {
+            let line = match tokio::time::timeout_at(deadline, self.receive_console_log()).await {
+                Ok(line) => line?,
+                Err(_) => return Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into()),
+            };
+            let line = strip_tags(&line).trim_end();
+
+            if !started {
+                started = is_status_header(line);
+                if !started {
+                    continue;
+                }
+            }
+
+            if line == "#end" {
+                return Ok(info);
+            }
+            in_players |= line.starts_with('#');
+            if !in_players {
+                info.update(line);
+            }
+        }
+    }
+}
+
+// Whether a line is the first one printed by `status`
+fn is_status_header(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(field, _)| field.trim() == "hostname")
+}
+
+/// Remove bracketed tags like `[12:34:56]` and `[SCRIPT SV]` from the start of a console log
+/// line.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_client::strip_tags;
+///
+/// assert_eq!(strip_tags("[12:34:56] [SCRIPT SV] Hello"), "Hello");
+/// ```
+pub fn strip_tags(line: &str) -> &str {
+    let mut line = line.trim_start();
+    while let Some((_, rest)) = line.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+        line = rest.trim_start();
+    }
+    line
+}
@@ -0,0 +1,312 @@
+//! Forward console logs to a syslog daemon.
+//!
+//! This module requires the `syslog` feature.
+
+use crate::{ClientRead, LogLevel};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, ToSocketAddrs, UdpSocket};
+
+// The longest hostname and application name allowed by RFC 5424
+const MAX_HOSTNAME_LEN: usize = 255;
+const MAX_APP_NAME_LEN: usize = 48;
+
+/// A syslog facility, describing the kind of program a message comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    User = 1,
+    Daemon = 3,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+#[derive(Debug)]
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixDatagram),
+}
+
+/// Sends console log lines to a syslog daemon as [RFC 5424] messages.
+///
+/// The severity of each message is taken from the level of the log line, as parsed by
+/// [`LogLevel::parse`]. Lines without a level are sent with the informational severity.
+///
+/// Messages can be sent over UDP, over TCP with octet-counting framing as described in
+/// [RFC 6587], or to a local Unix datagram socket such as `/dev/log`.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::connect;
+/// use northstar_rcon_client::syslog::{Facility, SyslogSink};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+///
+///     write.enable_console_logs().await.unwrap();
+///
+///     SyslogSink::connect_udp("localhost:514")
+///         .await
+///         .unwrap()
+///         .facility(Facility::Local0)
+///         .run(&mut read)
+///         .await
+///         .unwrap();
+/// }
+/// ```
+///
+/// [RFC 5424]: https://datatracker.ietf.org/doc/html/rfc5424
+/// [RFC 6587]: https://datatracker.ietf.org/doc/html/rfc6587#section-3.4.1
+#[derive(Debug)]
+pub struct SyslogSink {
+    transport: Transport,
+    facility: Facility,
+    hostname: String,
+    app_name: String,
+}
+
+impl SyslogSink {
+    /// Send messages to a syslog daemon over UDP.
+    pub async fn connect_udp<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let addr = tokio::net::lookup_host(addr)
+            .await?
+            .next()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+        let bind_addr: SocketAddr = if addr.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(addr).await?;
+        Ok(SyslogSink::new(Transport::Udp(socket)))
+    }
+
+    /// Send messages to a syslog daemon over TCP.
+    pub async fn connect_tcp<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(SyslogSink::new(Transport::Tcp(stream)))
+    }
+
+    /// Send messages to a local syslog daemon listening on a Unix datagram socket.
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let socket = tokio::net::UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(SyslogSink::new(Transport::Unix(socket)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        SyslogSink {
+            transport,
+            facility: Facility::User,
+            hostname: "-".to_string(),
+            app_name: "northstar".to_string(),
+        }
+    }
+
+    /// Set the facility messages are sent with. Defaults to [`Facility::User`].
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Set the hostname messages are sent with. By default no hostname is sent.
+    ///
+    /// Syslog only allows printable ASCII characters other than spaces, so anything else is
+    /// replaced with `_`, and the hostname is cut to 255 characters.
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = header_field(&hostname.into(), MAX_HOSTNAME_LEN);
+        self
+    }
+
+    /// Set the application name messages are sent with. Defaults to `northstar`.
+    ///
+    /// Like with [`hostname`](SyslogSink::hostname), characters syslog doesn't allow are
+    /// replaced with `_`, and the name is cut to 48 characters.
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = header_field(&app_name.into(), MAX_APP_NAME_LEN);
+        self
+    }
+
+    /// Send a single console log line.
+    pub async fn send(&mut self, line: &str) -> std::io::Result<()> {
+        let level = LogLevel::parse(line).unwrap_or(LogLevel::Info);
+        let priority = self.facility as u8 * 8 + severity(level);
+        let message = format!(
+            "<{}>1 {} {} {} - - - {}",
+            priority,
+            format_timestamp(SystemTime::now()),
+            self.hostname,
+            self.app_name,
+            line.trim_end()
+        );
+
+        match &mut self.transport {
+            Transport::Udp(socket) => {
+                socket.send(message.as_bytes()).await?;
+            }
+            Transport::Tcp(stream) => {
+                let frame = format!("{} {}", message.len(), message);
+                stream.write_all(frame.as_bytes()).await?;
+            }
+            #[cfg(unix)]
+            Transport::Unix(socket) => {
+                socket.send(message.as_bytes()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forward lines from a client until the connection fails.
+    ///
+    /// Console logs must be enabled separately, for example with
+    /// [`ClientWrite::enable_console_logs`].
+    ///
+    /// [`ClientWrite::enable_console_logs`]: crate::ClientWrite::enable_console_logs
+    pub async fn run(mut self, read: &mut ClientRead) -> crate::Result<()> {
+        loop {
+            let line = read.receive_console_log().await?;
+            self.send(&line).await?;
+        }
+    }
+}
+
+// Makes a value fit in a header field, which can only have printable ASCII characters other than
+// spaces. An empty field is written as `-`, which means it's missing.
+fn header_field(value: &str, max_len: usize) -> String {
+    if value.is_empty() {
+        return "-".to_string();
+    }
+    value
+        .chars()
+        .take(max_len)
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .collect()
+}
+
+fn severity(level: LogLevel) -> u8 {
+    match level {
+        LogLevel::Trace | LogLevel::Debug => 7,
+        LogLevel::Info => 6,
+        LogLevel::Warning => 4,
+        LogLevel::Error => 3,
+        LogLevel::Critical => 2,
+    }
+}
+
+// Formats a time as an RFC 3339 timestamp in UTC, with millisecond precision.
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// Converts days since the Unix epoch to a (year, month, day) date.
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    fn timestamp(secs: u64, millis: u64) -> String {
+        format_timestamp(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp(0, 0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(timestamp(951_782_400, 0), "2000-02-29T00:00:00.000Z");
+        assert_eq!(timestamp(1_735_689_599, 123), "2024-12-31T23:59:59.123Z");
+    }
+
+    #[test]
+    fn days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn header_fields() {
+        assert_eq!(header_field("eu server 1", 255), "eu_server_1");
+        assert_eq!(header_field("héllo\n", 255), "h_llo_");
+        assert_eq!(header_field(&"a".repeat(60), 48), "a".repeat(48));
+        assert_eq!(header_field("", 48), "-");
+    }
+
+    #[tokio::test]
+    async fn tcp_octet_counting() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut sink = SyslogSink::connect_tcp(listener.local_addr().unwrap())
+            .await
+            .unwrap()
+            .app_name("my app");
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        sink.send("[info] first\n").await.unwrap();
+        sink.send("[error] second").await.unwrap();
+        drop(sink);
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+
+        // Each frame is its length, a space, then exactly that many bytes of message
+        let mut messages = Vec::new();
+        let mut rest = received.as_str();
+        while !rest.is_empty() {
+            let (len, after) = rest.split_once(' ').unwrap();
+            let (message, after) = after.split_at(len.parse().unwrap());
+            messages.push(message);
+            rest = after;
+        }
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("<14>1 "));
+        assert!(messages[0].ends_with(" - my_app - - - [info] first"));
+        assert!(messages[1].starts_with("<11>1 "));
+        assert!(messages[1].ends_with(" - my_app - - - [error] second"));
+    }
+}
@@ -0,0 +1,158 @@
+//! A fake RCON server on a loopback socket, for tests.
+
+use crate::{ClientRead, ClientWrite};
+use bytes::{Buf, BytesMut};
+use northstar_rcon_types::raw;
+use northstar_rcon_types::split_frame;
+use protobuf::Message;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+pub struct TestServer {
+    listener: TcpListener,
+}
+
+/// A client connected to a [`TestServer`].
+pub struct TestConnection {
+    stream: TcpStream,
+    buffer: BytesMut,
+}
+
+impl TestServer {
+    pub async fn bind() -> Self {
+        TestServer {
+            listener: TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    pub async fn accept(&self) -> TestConnection {
+        let (stream, _) = self.listener.accept().await.unwrap();
+        TestConnection {
+            stream,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Accept a connection and accept the password it authenticates with.
+    pub async fn accept_authenticated(&self) -> TestConnection {
+        let mut connection = self.accept().await;
+        let request = connection.receive().await;
+        assert_eq!(
+            request_type(&request),
+            raw::Request_t::SERVERDATA_REQUEST_AUTH
+        );
+        connection
+            .send(
+                raw::Response_t::SERVERDATA_RESPONSE_AUTH,
+                "Authentication successful",
+            )
+            .await;
+        connection
+    }
+
+    /// Connect a client and authenticate it.
+    pub async fn connect(&self) -> (ClientRead, ClientWrite, TestConnection) {
+        let client = async {
+            let client = crate::connect(self.addr()).await.unwrap();
+            client.authenticate("password123").await.unwrap()
+        };
+        let ((read, write), connection) = tokio::join!(client, self.accept_authenticated());
+        (read, write, connection)
+    }
+}
+
+impl TestConnection {
+    /// Receive the next request, panicking if the connection closes first.
+    pub async fn receive(&mut self) -> raw::Request {
+        loop {
+            if let Some((frame, _)) = split_frame(&self.buffer) {
+                let request = raw::Request::parse_from_bytes(frame).unwrap();
+                let frame_len = frame.len();
+                self.buffer.advance(std::mem::size_of::<u32>() + frame_len);
+                return request;
+            }
+            let read = self.stream.read_buf(&mut self.buffer).await.unwrap();
+            assert_ne!(read, 0, "connection closed");
+        }
+    }
+
+    /// Receive the next request, which has to be a command, and return the command line.
+    pub async fn receive_command(&mut self) -> String {
+        let request = self.receive().await;
+        assert_eq!(
+            request_type(&request),
+            raw::Request_t::SERVERDATA_REQUEST_EXECCOMMAND
+        );
+        request.requestBuf.unwrap_or_default()
+    }
+
+    /// Check that nothing has been received, after giving the client a chance to send anything
+    /// it was going to.
+    pub async fn assert_idle(&mut self) {
+        assert!(self.buffer.is_empty(), "expected nothing to be received");
+
+        let mut byte = [0];
+        tokio::select! {
+            read = self.stream.peek(&mut byte) => {
+                panic!("expected nothing to be received, got {:?}", read);
+            }
+            () = settle() => {}
+        }
+    }
+
+    pub async fn send(&mut self, response_type: raw::Response_t, buf: &str) {
+        let mut response = raw::Response::new();
+        response.responseID = Some(-1);
+        response.responseType = Some(response_type.into());
+        response.responseBuf = Some(buf.to_string());
+
+        let frame = response.write_to_bytes().unwrap();
+        let mut bytes = (frame.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&frame);
+        self.stream.write_all(&bytes).await.unwrap();
+    }
+
+    pub async fn send_log(&mut self, line: &str) {
+        self.send(
+            raw::Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG,
+            &format!("{}\n", line),
+        )
+        .await;
+    }
+}
+
+pub fn request_type(request: &raw::Request) -> raw::Request_t {
+    request.requestType.unwrap().enum_value().unwrap()
+}
+
+/// Keep the runtime busy, so that paused time only moves with
+/// [`advance`](tokio::time::advance).
+///
+/// When the runtime is idle with paused time, tokio moves the clock on to the next timer even
+/// while loopback IO is still being delivered, which makes timings in tests unpredictable.
+pub fn hold_clock() -> JoinHandle<()> {
+    tokio::spawn(async {
+        loop {
+            tokio::task::yield_now().await;
+        }
+    })
+}
+
+// Give the client a chance to send anything it was going to. This waits in real time rather
+// than paused time, since a small request can be held back by Nagle's algorithm until the last one
+// is acknowledged, which the kernel can delay by tens of milliseconds.
+async fn settle() {
+    let start = Instant::now();
+    while start.elapsed() < SETTLE_TIME {
+        tokio::task::yield_now().await;
+    }
+}
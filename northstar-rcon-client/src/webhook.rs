@@ -0,0 +1,167 @@
+//! Notify an HTTP endpoint when console log lines match a pattern.
+//!
+//! This module requires the `webhook` feature.
+
+use crate::{ClientRead, LogLevel};
+use regex::Regex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An error describing why a webhook notification failed.
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    /// The webhook request failed after all retries, or was rejected by the endpoint.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// Reading logs from the RCON server failed.
+    #[error(transparent)]
+    Client(#[from] crate::Error),
+}
+
+/// POSTs a JSON payload to a URL whenever a console log line matches one of a set of patterns.
+///
+/// The payload is a JSON object with these fields:
+/// - `line`: the full log line.
+/// - `pattern`: the pattern that matched.
+/// - `level`: the level of the line as parsed by [`LogLevel::parse`], or `null` if it has none.
+/// - `timestamp`: when the line was received, in seconds since the Unix epoch.
+///
+/// If a request fails because of a connection problem or a server error, it's retried with
+/// exponential backoff. Other error statuses are returned immediately.
+///
+/// # Example
+/// ```rust,no_run
+/// use northstar_rcon_client::connect;
+/// use northstar_rcon_client::webhook::WebhookNotifier;
+/// use regex::Regex;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = connect("localhost:37015").await.unwrap();
+///     let (mut read, mut write) = client.authenticate("password123").await.unwrap();
+///
+///     write.enable_console_logs().await.unwrap();
+///
+///     WebhookNotifier::new("https://example.com/hooks/northstar")
+///         .pattern(Regex::new("(?i)script error").unwrap())
+///         .run(&mut read)
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    http: reqwest::Client,
+    url: String,
+    patterns: Vec<Regex>,
+    max_retries: u32,
+    initial_backoff: Duration,
+}
+
+impl WebhookNotifier {
+    /// Create a notifier that posts to a URL.
+    ///
+    /// The notifier has no patterns, so nothing is posted until at least one is added with
+    /// [`pattern`]. By default failed requests are retried 3 times, starting with a 1 second
+    /// delay.
+    ///
+    /// [`pattern`]: WebhookNotifier::pattern
+    pub fn new(url: impl Into<String>) -> Self {
+        WebhookNotifier {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            patterns: Vec::new(),
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Post a notification for lines matching this pattern.
+    pub fn pattern(mut self, pattern: Regex) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Set how many times a failed request is retried before giving up.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry. The delay doubles after each failed retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Post a notification if the line matches any pattern.
+    ///
+    /// Returns whether the line matched.
+    pub async fn notify(&self, line: &str) -> Result<bool, WebhookError> {
+        let pattern = match self.patterns.iter().find(|pattern| pattern.is_match(line)) {
+            Some(pattern) => pattern,
+            None => return Ok(false),
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let body = serde_json::json!({
+            "line": line,
+            "pattern": pattern.as_str(),
+            "level": LogLevel::parse(line).map(LogLevel::name),
+            "timestamp": timestamp,
+        });
+
+        self.post(&body).await?;
+        Ok(true)
+    }
+
+    /// Check lines from a client until the connection or a notification fails.
+    ///
+    /// Console logs must be enabled separately, for example with
+    /// [`ClientWrite::enable_console_logs`].
+    ///
+    /// [`ClientWrite::enable_console_logs`]: crate::ClientWrite::enable_console_logs
+    pub async fn run(self, read: &mut ClientRead) -> Result<(), WebhookError> {
+        loop {
+            let line = read.receive_console_log().await?;
+            self.notify(&line).await?;
+        }
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> Result<(), WebhookError> {
+        let mut backoff = self.initial_backoff;
+        let mut retries = 0;
+
+        loop {
+            let result = self
+                .http
+                .post(&self.url)
+                .json(body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => return Ok(()),
+                Err(err) if retries < self.max_retries && is_retryable(&err) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    retries += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+fn is_retryable(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }
+        None => err.is_connect() || err.is_timeout() || err.is_request(),
+    }
+}
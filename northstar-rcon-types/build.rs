@@ -0,0 +1,13 @@
+// The generated code is checked in to `src/generated`, so protoc-style codegen only runs with the
+// `regen-protos` feature. Copy the output from `OUT_DIR/protos` over `src/generated` after
+// changing the protocol.
+fn main() {
+    #[cfg(feature = "regen-protos")]
+    protobuf_codegen::Codegen::new()
+        .pure()
+        .cargo_out_dir("protos")
+        .input("protos/cl_rcon.proto")
+        .input("protos/sv_rcon.proto")
+        .include("protos")
+        .run_from_script();
+}
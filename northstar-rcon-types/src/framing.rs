@@ -0,0 +1,146 @@
+use protobuf::Message;
+use std::ops::Range;
+
+/// Encode a request onto the end of a buffer, prefixed with its length, ready to be sent.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::{encode_request, raw, Request};
+///
+/// let mut buf = Vec::new();
+/// encode_request(&raw::Request::from(Request::ExecCommand { cmd: "status" }), &mut buf).unwrap();
+/// assert_eq!(u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize, buf.len() - 4);
+/// ```
+pub fn encode_request(request: &crate::raw::Request, buf: &mut Vec<u8>) -> protobuf::Result<()> {
+    let len = request.compute_size() as u32;
+    buf.extend_from_slice(&len.to_be_bytes());
+    request.write_to_vec(buf)
+}
+
+/// Split the first frame from received bytes, returning the frame without its length and the
+/// bytes after it.
+///
+/// Returns `None` if the whole frame hasn't been received yet.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::split_frame;
+///
+/// assert_eq!(split_frame(&[0, 0, 0, 2, 7, 8, 9]), Some((&[7, 8][..], &[9][..])));
+/// assert_eq!(split_frame(&[0, 0, 0, 2, 7]), None);
+/// ```
+pub fn split_frame(received: &[u8]) -> Option<(&[u8], &[u8])> {
+    if received.len() < 4 {
+        return None;
+    }
+
+    let (len_bytes, remaining_bytes) = received.split_at(std::mem::size_of::<u32>());
+
+    let len = u32::from_be_bytes(len_bytes.try_into().unwrap());
+    if remaining_bytes.len() < len as usize {
+        return None;
+    }
+
+    Some(remaining_bytes.split_at(len as usize))
+}
+
+/// Decode a frame returned by [`split_frame`] into a response.
+///
+/// Fields the protobuf definitions don't have, such as ones added by newer servers, are kept in
+/// the response's `special_fields`.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::decode_response;
+///
+/// // responseType 3, then field 9 (unknown) with the varint 1
+/// let response = decode_response(&[0x10, 0x03, 0x48, 0x01]).unwrap();
+///
+/// let unknown: Vec<u32> = response
+///     .special_fields
+///     .unknown_fields()
+///     .iter()
+///     .map(|(field, _)| field)
+///     .collect();
+/// assert_eq!(unknown, [9]);
+/// ```
+pub fn decode_response(frame: &[u8]) -> protobuf::Result<crate::raw::Response> {
+    crate::raw::Response::parse_from(&mut protobuf::CodedInputStream::from_bytes(frame))
+}
+
+/// Find the console log line in a frame returned by [`split_frame`] without decoding the rest of
+/// it, returning where the line's bytes are in the frame.
+///
+/// Returns `None` if the frame isn't a console log response. The line isn't checked to be valid
+/// UTF-8. This lets a line be passed on without copying it out of the received bytes.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::{console_log_range, protobuf::Message, raw};
+///
+/// let mut response = raw::Response::new();
+/// response.responseType = Some(raw::Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG.into());
+/// response.responseBuf = Some("Hello".to_string());
+/// let frame = response.write_to_bytes().unwrap();
+///
+/// let range = console_log_range(&frame).unwrap();
+/// assert_eq!(&frame[range], b"Hello");
+/// ```
+pub fn console_log_range(frame: &[u8]) -> Option<Range<usize>> {
+    let mut response_type = None;
+    let mut buf = None;
+
+    let mut offset = 0;
+    while offset < frame.len() {
+        let tag = read_varint(frame, &mut offset)?;
+        match (tag >> 3, tag & 0b111) {
+            (field, WIRE_VARINT) => {
+                let value = read_varint(frame, &mut offset)?;
+                if field == RESPONSE_TYPE_FIELD {
+                    response_type = Some(value);
+                }
+            }
+            (field, WIRE_LEN) => {
+                let len = usize::try_from(read_varint(frame, &mut offset)?).ok()?;
+                let end = offset.checked_add(len).filter(|&end| end <= frame.len())?;
+                if field == RESPONSE_BUF_FIELD {
+                    buf = Some(offset..end);
+                }
+                offset = end;
+            }
+            (_, WIRE_FIXED64) => offset += 8,
+            (_, WIRE_FIXED32) => offset += 4,
+            _ => return None,
+        }
+    }
+    if offset > frame.len() {
+        return None;
+    }
+
+    let console_log = crate::raw::Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG as u64;
+    match response_type {
+        Some(response_type) if response_type == console_log => buf,
+        _ => None,
+    }
+}
+
+// Field numbers and wire types from the protobuf encoding of `sv_rcon.response`
+const RESPONSE_TYPE_FIELD: u64 = 2;
+const RESPONSE_BUF_FIELD: u64 = 3;
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -0,0 +1,209 @@
+// This file is generated by rust-protobuf 3.1.0. Do not edit
+// .proto file is parsed by pure
+// @generated
+
+// https://github.com/rust-lang/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy::all)]
+
+#![allow(unused_attributes)]
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unused_results)]
+#![allow(unused_mut)]
+
+//! Generated file from `cl_rcon.proto`
+// Generated for lite runtime
+
+/// Generated files are compatible only with the same version
+/// of protobuf runtime.
+const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_1_0;
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:cl_rcon.request)
+pub struct Request {
+    // message fields
+    // @@protoc_insertion_point(field:cl_rcon.request.requestID)
+    pub requestID: ::std::option::Option<i32>,
+    // @@protoc_insertion_point(field:cl_rcon.request.requestType)
+    pub requestType: ::std::option::Option<::protobuf::EnumOrUnknown<Request_t>>,
+    // @@protoc_insertion_point(field:cl_rcon.request.requestBuf)
+    pub requestBuf: ::std::option::Option<::std::string::String>,
+    // @@protoc_insertion_point(field:cl_rcon.request.requestVal)
+    pub requestVal: ::std::option::Option<::std::string::String>,
+    // special fields
+    // @@protoc_insertion_point(special_field:cl_rcon.request.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a Request {
+    fn default() -> &'a Request {
+        <Request as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Request {
+    pub fn new() -> Request {
+        ::std::default::Default::default()
+    }
+}
+
+impl ::protobuf::Message for Request {
+    const NAME: &'static str = "request";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.requestID = ::std::option::Option::Some(is.read_int32()?);
+                },
+                16 => {
+                    self.requestType = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                },
+                26 => {
+                    self.requestBuf = ::std::option::Option::Some(is.read_string()?);
+                },
+                34 => {
+                    self.requestVal = ::std::option::Option::Some(is.read_string()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.requestID {
+            my_size += ::protobuf::rt::int32_size(1, v);
+        }
+        if let Some(v) = self.requestType {
+            my_size += ::protobuf::rt::int32_size(2, v.value());
+        }
+        if let Some(v) = self.requestBuf.as_ref() {
+            my_size += ::protobuf::rt::string_size(3, &v);
+        }
+        if let Some(v) = self.requestVal.as_ref() {
+            my_size += ::protobuf::rt::string_size(4, &v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.requestID {
+            os.write_int32(1, v)?;
+        }
+        if let Some(v) = self.requestType {
+            os.write_enum(2, ::protobuf::EnumOrUnknown::value(&v))?;
+        }
+        if let Some(v) = self.requestBuf.as_ref() {
+            os.write_string(3, v)?;
+        }
+        if let Some(v) = self.requestVal.as_ref() {
+            os.write_string(4, v)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> Request {
+        Request::new()
+    }
+
+    fn clear(&mut self) {
+        self.requestID = ::std::option::Option::None;
+        self.requestType = ::std::option::Option::None;
+        self.requestBuf = ::std::option::Option::None;
+        self.requestVal = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static Request {
+        static instance: Request = Request {
+            requestID: ::std::option::Option::None,
+            requestType: ::std::option::Option::None,
+            requestBuf: ::std::option::Option::None,
+            requestVal: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:cl_rcon.request_t)
+pub enum Request_t {
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_VALUE)
+    SERVERDATA_REQUEST_VALUE = 0,
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_SETVALUE)
+    SERVERDATA_REQUEST_SETVALUE = 1,
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_EXECCOMMAND)
+    SERVERDATA_REQUEST_EXECCOMMAND = 2,
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_AUTH)
+    SERVERDATA_REQUEST_AUTH = 3,
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_SEND_CONSOLE_LOG)
+    SERVERDATA_REQUEST_SEND_CONSOLE_LOG = 4,
+    // @@protoc_insertion_point(enum_value:cl_rcon.request_t.SERVERDATA_REQUEST_SEND_REMOTEBUG)
+    SERVERDATA_REQUEST_SEND_REMOTEBUG = 5,
+}
+
+impl ::protobuf::Enum for Request_t {
+    const NAME: &'static str = "request_t";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<Request_t> {
+        match value {
+            0 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_VALUE),
+            1 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_SETVALUE),
+            2 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_EXECCOMMAND),
+            3 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_AUTH),
+            4 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG),
+            5 => ::std::option::Option::Some(Request_t::SERVERDATA_REQUEST_SEND_REMOTEBUG),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [Request_t] = &[
+        Request_t::SERVERDATA_REQUEST_VALUE,
+        Request_t::SERVERDATA_REQUEST_SETVALUE,
+        Request_t::SERVERDATA_REQUEST_EXECCOMMAND,
+        Request_t::SERVERDATA_REQUEST_AUTH,
+        Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG,
+        Request_t::SERVERDATA_REQUEST_SEND_REMOTEBUG,
+    ];
+}
+
+impl ::std::default::Default for Request_t {
+    fn default() -> Self {
+        Request_t::SERVERDATA_REQUEST_VALUE
+    }
+}
+
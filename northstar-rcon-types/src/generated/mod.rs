@@ -0,0 +1,4 @@
+// @generated
+
+pub mod cl_rcon;
+pub mod sv_rcon;
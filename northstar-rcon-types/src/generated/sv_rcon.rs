@@ -0,0 +1,209 @@
+// This file is generated by rust-protobuf 3.1.0. Do not edit
+// .proto file is parsed by pure
+// @generated
+
+// https://github.com/rust-lang/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy::all)]
+
+#![allow(unused_attributes)]
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unused_results)]
+#![allow(unused_mut)]
+
+//! Generated file from `sv_rcon.proto`
+// Generated for lite runtime
+
+/// Generated files are compatible only with the same version
+/// of protobuf runtime.
+const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_1_0;
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:sv_rcon.response)
+pub struct Response {
+    // message fields
+    // @@protoc_insertion_point(field:sv_rcon.response.responseID)
+    pub responseID: ::std::option::Option<i32>,
+    // @@protoc_insertion_point(field:sv_rcon.response.responseType)
+    pub responseType: ::std::option::Option<::protobuf::EnumOrUnknown<Response_t>>,
+    // @@protoc_insertion_point(field:sv_rcon.response.responseBuf)
+    pub responseBuf: ::std::option::Option<::std::string::String>,
+    // @@protoc_insertion_point(field:sv_rcon.response.responseVal)
+    pub responseVal: ::std::option::Option<::std::string::String>,
+    // special fields
+    // @@protoc_insertion_point(special_field:sv_rcon.response.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a Response {
+    fn default() -> &'a Response {
+        <Response as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Response {
+    pub fn new() -> Response {
+        ::std::default::Default::default()
+    }
+}
+
+impl ::protobuf::Message for Response {
+    const NAME: &'static str = "response";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.responseID = ::std::option::Option::Some(is.read_int32()?);
+                },
+                16 => {
+                    self.responseType = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                },
+                26 => {
+                    self.responseBuf = ::std::option::Option::Some(is.read_string()?);
+                },
+                34 => {
+                    self.responseVal = ::std::option::Option::Some(is.read_string()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.responseID {
+            my_size += ::protobuf::rt::int32_size(1, v);
+        }
+        if let Some(v) = self.responseType {
+            my_size += ::protobuf::rt::int32_size(2, v.value());
+        }
+        if let Some(v) = self.responseBuf.as_ref() {
+            my_size += ::protobuf::rt::string_size(3, &v);
+        }
+        if let Some(v) = self.responseVal.as_ref() {
+            my_size += ::protobuf::rt::string_size(4, &v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.responseID {
+            os.write_int32(1, v)?;
+        }
+        if let Some(v) = self.responseType {
+            os.write_enum(2, ::protobuf::EnumOrUnknown::value(&v))?;
+        }
+        if let Some(v) = self.responseBuf.as_ref() {
+            os.write_string(3, v)?;
+        }
+        if let Some(v) = self.responseVal.as_ref() {
+            os.write_string(4, v)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> Response {
+        Response::new()
+    }
+
+    fn clear(&mut self) {
+        self.responseID = ::std::option::Option::None;
+        self.responseType = ::std::option::Option::None;
+        self.responseBuf = ::std::option::Option::None;
+        self.responseVal = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static Response {
+        static instance: Response = Response {
+            responseID: ::std::option::Option::None,
+            responseType: ::std::option::Option::None,
+            responseBuf: ::std::option::Option::None,
+            responseVal: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:sv_rcon.response_t)
+pub enum Response_t {
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_VALUE)
+    SERVERDATA_RESPONSE_VALUE = 0,
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_UPDATE)
+    SERVERDATA_RESPONSE_UPDATE = 1,
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_AUTH)
+    SERVERDATA_RESPONSE_AUTH = 2,
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_CONSOLE_LOG)
+    SERVERDATA_RESPONSE_CONSOLE_LOG = 3,
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_STRING)
+    SERVERDATA_RESPONSE_STRING = 4,
+    // @@protoc_insertion_point(enum_value:sv_rcon.response_t.SERVERDATA_RESPONSE_REMOTEBUG)
+    SERVERDATA_RESPONSE_REMOTEBUG = 5,
+}
+
+impl ::protobuf::Enum for Response_t {
+    const NAME: &'static str = "response_t";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<Response_t> {
+        match value {
+            0 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_VALUE),
+            1 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_UPDATE),
+            2 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_AUTH),
+            3 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG),
+            4 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_STRING),
+            5 => ::std::option::Option::Some(Response_t::SERVERDATA_RESPONSE_REMOTEBUG),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [Response_t] = &[
+        Response_t::SERVERDATA_RESPONSE_VALUE,
+        Response_t::SERVERDATA_RESPONSE_UPDATE,
+        Response_t::SERVERDATA_RESPONSE_AUTH,
+        Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG,
+        Response_t::SERVERDATA_RESPONSE_STRING,
+        Response_t::SERVERDATA_RESPONSE_REMOTEBUG,
+    ];
+}
+
+impl ::std::default::Default for Response_t {
+    fn default() -> Self {
+        Response_t::SERVERDATA_RESPONSE_VALUE
+    }
+}
+
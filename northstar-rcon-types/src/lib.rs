@@ -0,0 +1,38 @@
+//! The protocol types used by [Northstar mod]'s RCON server, without any networking.
+//!
+//! This crate has the messages that can be sent and received, functions for splitting and
+//! building the length-prefixed frames they're sent in, and [`LogLevel`] for console log lines.
+//! It doesn't do any IO, so it can be used by anything that speaks the protocol without pulling
+//! in an async runtime. For a client, see the `northstar-rcon-client` crate.
+//!
+//! # Example
+//! ```rust
+//! use northstar_rcon_types::{decode_response, split_frame, Response};
+//!
+//! fn handle_received(received: &[u8]) {
+//!     let mut remaining = received;
+//!     while let Some((frame, rest)) = split_frame(remaining) {
+//!         let response = decode_response(frame).unwrap();
+//!         if let Ok(Response::ConsoleLog { msg }) = Response::try_from(response) {
+//!             println!("> {}", msg);
+//!         }
+//!         remaining = rest;
+//!     }
+//! }
+//! ```
+//!
+//! # Features
+//! - `regen-protos`: generate the [`raw`] types from the protobuf definitions when building,
+//!   instead of using the generated code that's checked in.
+//!
+//! [Northstar mod]: https://northstar.tf/
+
+mod framing;
+mod log;
+mod message;
+pub mod raw;
+
+pub use self::framing::*;
+pub use self::log::*;
+pub use self::message::*;
+pub use protobuf;
@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+
+/// Severity of a console log line.
+///
+/// Northstar formats its console output as `[12:34:56] [NORTHSTAR] [info] message`, so the level
+/// can be recovered from the bracketed tags at the start of a line. Levels are ordered from least
+/// to most severe, so they can be compared to filter lines.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::LogLevel;
+///
+/// let level = LogLevel::parse("[12:34:56] [NORTHSTAR] [warning] Mod failed to load");
+/// assert_eq!(level, Some(LogLevel::Warning));
+/// assert!(LogLevel::Warning > LogLevel::Info);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+// Only this many bracketed tags at the start of a line are checked for a level, so bracketed
+// text in the message itself isn't mistaken for one.
+const MAX_LEVEL_TAG_INDEX: usize = 4;
+
+impl LogLevel {
+    /// Find the level of a console log line, if it has one.
+    ///
+    /// Returns `None` if the line doesn't start with a recognized level tag.
+    pub fn parse(line: &str) -> Option<LogLevel> {
+        let mut remaining = line.trim_start();
+
+        for _ in 0..MAX_LEVEL_TAG_INDEX {
+            let tag_end = remaining.strip_prefix('[')?.find(']')? + 1;
+            if let Some(level) = LogLevel::from_name(&remaining[1..tag_end]) {
+                return Some(level);
+            }
+
+            remaining = remaining[tag_end + 1..].trim_start();
+        }
+
+        None
+    }
+
+    /// Get the level matching a name, such as `info` or `WARN`.
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "trace" => Some(LogLevel::Trace),
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warning),
+            "error" | "err" => Some(LogLevel::Error),
+            "critical" | "fatal" => Some(LogLevel::Critical),
+            _ => None,
+        }
+    }
+
+    /// The lowercase name of the level, as Northstar prints it.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Critical => "critical",
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
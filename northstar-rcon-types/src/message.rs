@@ -0,0 +1,272 @@
+use std::sync::OnceLock;
+
+/// The request ID sent when no other ID is chosen.
+pub const DEFAULT_REQUEST_ID: i32 = -1;
+
+/// A request that can be sent to the server.
+///
+/// Converting it into a [`raw::Request`](crate::raw::Request) gives it the
+/// [`DEFAULT_REQUEST_ID`].
+#[derive(Debug, Clone, Copy)]
+pub enum Request<'a> {
+    /// Authenticate with a password.
+    Auth { pass: &'a str },
+
+    /// Set the value of a ConVar.
+    SetValue { var: &'a str, val: &'a str },
+
+    /// Execute a command.
+    ExecCommand { cmd: &'a str },
+
+    /// Start sending console logs.
+    EnableConsoleLogs,
+}
+
+/// A response from the server that's understood.
+#[derive(Debug)]
+pub enum Response {
+    /// The result of an authentication request, and the message the server sent with it.
+    Auth {
+        res: Result<(), AuthError>,
+        msg: String,
+    },
+
+    /// A console log line.
+    ConsoleLog { msg: String },
+}
+
+/// Why the server rejected an authentication request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    InvalidPassword,
+    Banned,
+}
+
+/// Text the server puts in authentication responses, used to tell why authentication failed.
+///
+/// A response containing any of the `invalid_password` strings means the password was wrong, and
+/// any of the `banned` strings means the client is banned. Anything else means authentication
+/// succeeded. The default matches the messages Northstar sends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthMatchers {
+    pub invalid_password: Vec<String>,
+    pub banned: Vec<String>,
+
+    /// Whether to read a result code from the response's value when none of the text matches,
+    /// with [`AuthOutcome::from_code`]. No server sends these yet, so this is off by default.
+    pub result_codes: bool,
+}
+
+impl Default for AuthMatchers {
+    fn default() -> Self {
+        AuthMatchers {
+            invalid_password: vec!["Admin password incorrect".to_string()],
+            banned: vec!["Go away".to_string()],
+            result_codes: false,
+        }
+    }
+}
+
+/// The result of an authentication request, read from the server's response.
+///
+/// # Example
+/// ```rust
+/// use northstar_rcon_types::{AuthMatchers, AuthOutcome};
+///
+/// let matchers = AuthMatchers::default();
+/// let outcome = AuthOutcome::parse("Admin password incorrect.", None, &matchers);
+/// assert_eq!(outcome, AuthOutcome::InvalidPassword);
+///
+/// // Result codes are only read when enabled, and when none of the text matches
+/// let matchers = AuthMatchers { result_codes: true, ..AuthMatchers::default() };
+/// let outcome = AuthOutcome::parse("Admin password incorrect.", Some("0"), &matchers);
+/// assert_eq!(outcome, AuthOutcome::InvalidPassword);
+/// let outcome = AuthOutcome::parse("Rejected", Some("2"), &matchers);
+/// assert_eq!(outcome, AuthOutcome::Banned);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthOutcome {
+    Accepted,
+    InvalidPassword,
+    Banned,
+}
+
+impl AuthOutcome {
+    /// Read the outcome from an authentication response's buffer and value.
+    ///
+    /// The outcome is found by looking for the `matchers` in the buffer, which is how current
+    /// servers report it. If none match and [`result_codes`] is enabled, a result code in the value
+    /// understood by [`from_code`] is used. Otherwise authentication succeeded.
+    ///
+    /// [`from_code`]: AuthOutcome::from_code
+    /// [`result_codes`]: AuthMatchers::result_codes
+    pub fn parse(buf: &str, val: Option<&str>, matchers: &AuthMatchers) -> AuthOutcome {
+        // The text wins over a code, so a code can never turn a rejection into a success
+        let contains_any = |patterns: &[String]| patterns.iter().any(|p| buf.contains(p.as_str()));
+        if contains_any(&matchers.invalid_password) {
+            return AuthOutcome::InvalidPassword;
+        }
+        if contains_any(&matchers.banned) {
+            return AuthOutcome::Banned;
+        }
+
+        let code = val
+            .filter(|_| matchers.result_codes)
+            .and_then(|val| val.trim().parse().ok());
+        code.and_then(AuthOutcome::from_code)
+            .unwrap_or(AuthOutcome::Accepted)
+    }
+
+    /// Get the outcome for a structured result code: `0` for accepted, `1` for an invalid
+    /// password and `2` for banned. Returns `None` for any other code.
+    ///
+    /// No server defines these codes yet, so they're only a guess at what one might send.
+    pub fn from_code(code: i64) -> Option<AuthOutcome> {
+        match code {
+            0 => Some(AuthOutcome::Accepted),
+            1 => Some(AuthOutcome::InvalidPassword),
+            2 => Some(AuthOutcome::Banned),
+            _ => None,
+        }
+    }
+
+    /// Convert the outcome to the result stored in [`Response::Auth`].
+    pub fn into_result(self) -> Result<(), AuthError> {
+        match self {
+            AuthOutcome::Accepted => Ok(()),
+            AuthOutcome::InvalidPassword => Err(AuthError::InvalidPassword),
+            AuthOutcome::Banned => Err(AuthError::Banned),
+        }
+    }
+}
+
+impl From<Request<'_>> for crate::raw::Request {
+    fn from(request: Request<'_>) -> Self {
+        let (request_type, request_buf, request_val) = match request {
+            Request::Auth { pass, .. } => (
+                crate::raw::Request_t::SERVERDATA_REQUEST_AUTH,
+                Some(pass.to_string()),
+                None,
+            ),
+            Request::SetValue { var, val, .. } => (
+                crate::raw::Request_t::SERVERDATA_REQUEST_SETVALUE,
+                Some(var.to_string()),
+                Some(val.to_string()),
+            ),
+            Request::ExecCommand { cmd, .. } => (
+                crate::raw::Request_t::SERVERDATA_REQUEST_EXECCOMMAND,
+                Some(cmd.to_string()),
+                None,
+            ),
+            Request::EnableConsoleLogs => (
+                crate::raw::Request_t::SERVERDATA_REQUEST_SEND_CONSOLE_LOG,
+                None,
+                None,
+            ),
+        };
+
+        crate::raw::Request {
+            requestID: Some(DEFAULT_REQUEST_ID),
+            requestType: Some(protobuf::EnumOrUnknown::new(request_type)),
+            requestBuf: request_buf,
+            requestVal: request_val,
+            special_fields: protobuf::SpecialFields::default(),
+        }
+    }
+}
+
+impl Response {
+    /// Convert a raw response, using `matchers` to read authentication responses.
+    ///
+    /// Returns `None` for responses that aren't understood, which can be skipped.
+    pub fn from_raw(value: crate::raw::Response, matchers: &AuthMatchers) -> Option<Self> {
+        let proto_response_type = value.responseType?.enum_value().ok()?;
+
+        match proto_response_type {
+            crate::raw::Response_t::SERVERDATA_RESPONSE_AUTH => {
+                let message: String = value.responseBuf?;
+                let outcome = AuthOutcome::parse(&message, value.responseVal.as_deref(), matchers);
+
+                Some(Response::Auth {
+                    res: outcome.into_result(),
+                    msg: message,
+                })
+            }
+            crate::raw::Response_t::SERVERDATA_RESPONSE_CONSOLE_LOG => Some(Response::ConsoleLog {
+                msg: value.responseBuf?,
+            }),
+
+            crate::raw::Response_t::SERVERDATA_RESPONSE_VALUE
+            | crate::raw::Response_t::SERVERDATA_RESPONSE_UPDATE
+            | crate::raw::Response_t::SERVERDATA_RESPONSE_STRING
+            | crate::raw::Response_t::SERVERDATA_RESPONSE_REMOTEBUG => {
+                // Unknown/unused?
+                None
+            }
+        }
+    }
+}
+
+/// Fails for responses that aren't understood, which can be skipped. Authentication responses are
+/// read with the default [`AuthMatchers`].
+impl TryFrom<crate::raw::Response> for Response {
+    type Error = ();
+
+    fn try_from(value: crate::raw::Response) -> Result<Self, Self::Error> {
+        static DEFAULT_MATCHERS: OnceLock<AuthMatchers> = OnceLock::new();
+        let matchers = DEFAULT_MATCHERS.get_or_init(AuthMatchers::default);
+        Response::from_raw(value, matchers).ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthMatchers, AuthOutcome};
+
+    const INCORRECT: &str = "Admin password incorrect.";
+
+    #[test]
+    fn codes_are_ignored_by_default() {
+        let matchers = AuthMatchers::default();
+        for val in [None, Some("0"), Some("1"), Some("2")] {
+            assert_eq!(
+                AuthOutcome::parse(INCORRECT, val, &matchers),
+                AuthOutcome::InvalidPassword
+            );
+            assert_eq!(
+                AuthOutcome::parse("Authentication successful", val, &matchers),
+                AuthOutcome::Accepted
+            );
+        }
+    }
+
+    #[test]
+    fn matchers_win_over_codes() {
+        let matchers = AuthMatchers {
+            result_codes: true,
+            ..AuthMatchers::default()
+        };
+        assert_eq!(
+            AuthOutcome::parse(INCORRECT, Some("0"), &matchers),
+            AuthOutcome::InvalidPassword
+        );
+        assert_eq!(
+            AuthOutcome::parse("Go away", Some("0"), &matchers),
+            AuthOutcome::Banned
+        );
+    }
+
+    #[test]
+    fn codes_are_used_when_enabled_and_nothing_matches() {
+        let matchers = AuthMatchers {
+            result_codes: true,
+            ..AuthMatchers::default()
+        };
+        let parse = |val| AuthOutcome::parse("Rejected", val, &matchers);
+        assert_eq!(parse(Some("1")), AuthOutcome::InvalidPassword);
+        assert_eq!(parse(Some(" 2 ")), AuthOutcome::Banned);
+        assert_eq!(parse(Some("0")), AuthOutcome::Accepted);
+        assert_eq!(parse(Some("7")), AuthOutcome::Accepted);
+        assert_eq!(parse(None), AuthOutcome::Accepted);
+    }
+}
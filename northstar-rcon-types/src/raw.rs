@@ -0,0 +1,14 @@
+//! The types generated from the server's protobuf definitions.
+//!
+//! These may change between versions of this crate if the server's protocol changes.
+
+// The generated code allows lints that newer compilers no longer know about.
+#![allow(renamed_and_removed_lints)]
+
+#[cfg(feature = "regen-protos")]
+include!(concat!(env!("OUT_DIR"), "/protos/mod.rs"));
+#[cfg(not(feature = "regen-protos"))]
+include!("generated/mod.rs");
+
+pub use self::cl_rcon::*;
+pub use self::sv_rcon::*;